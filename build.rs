@@ -7,7 +7,10 @@ fn main() {
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
 
-    if target_os == "linux" && target_arch == "aarch64" {
+    // Only the `wakeword` feature links TensorFlow Lite
+    let wakeword = env::var_os("CARGO_FEATURE_WAKEWORD").is_some();
+
+    if wakeword && target_os == "linux" && target_arch == "aarch64" {
         println!(
             "cargo:warning=🔍 Linux aarch64 detected - using custom TensorFlow Lite libraries"
         );
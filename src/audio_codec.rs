@@ -0,0 +1,303 @@
+//! Compressed encodings of the consumer port's 16kHz mono s16le audio.
+//!
+//! Raw audio costs 256 kbit/s, which adds up when the agent runs on another
+//! host over Wi-Fi. A consumer that negotiates `CAP_AUDIO_ULAW` or
+//! `CAP_AUDIO_ADPCM` receives `Audio` and `PrerollAudio` data in that codec
+//! instead:
+//!
+//! - `Ulaw`: G.711 µ-law, one byte per sample (128 kbit/s)
+//! - `ImaAdpcm`: IMA ADPCM, four bits per sample (64 kbit/s). Each chunk starts
+//!   with the decoder state, `[predictor: i16][step_index: u8][flags: u8]`, so
+//!   chunks decode on their own even when a lagging consumer drops some. Bit 0
+//!   of `flags` marks a padding nibble after an odd number of samples.
+//!
+//! Encoding keeps state across chunks for quality; decoding needs none.
+
+use crate::protocol::ProtocolError;
+use std::fmt;
+
+/// Encoding of consumer audio payloads, chosen by the negotiated capabilities
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AudioCodec {
+    /// 16kHz mono s16le, as captured
+    #[default]
+    Raw,
+    Ulaw,
+    ImaAdpcm,
+}
+
+impl fmt::Display for AudioCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioCodec::Raw => write!(f, "raw"),
+            AudioCodec::Ulaw => write!(f, "ulaw"),
+            AudioCodec::ImaAdpcm => write!(f, "ima-adpcm"),
+        }
+    }
+}
+
+impl AudioCodec {
+    /// Decode one chunk back to s16le
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        match self {
+            AudioCodec::Raw => Ok(data.to_vec()),
+            AudioCodec::Ulaw => Ok(data
+                .iter()
+                .flat_map(|&b| ulaw_decode(b).to_le_bytes())
+                .collect()),
+            AudioCodec::ImaAdpcm => {
+                if data.len() < ADPCM_HEADER_BYTES {
+                    return Err(ProtocolError::InvalidPayloadSize(data.len() as u32));
+                }
+                let mut state = AdpcmState {
+                    predictor: i16::from_le_bytes([data[0], data[1]]) as i32,
+                    index: (data[2] as usize).min(STEP_TABLE.len() - 1),
+                };
+                let padded = data[3] & 1 != 0;
+                if padded && data.len() == ADPCM_HEADER_BYTES {
+                    return Err(ProtocolError::InvalidPayloadSize(data.len() as u32));
+                }
+
+                let nibbles = data[ADPCM_HEADER_BYTES..]
+                    .iter()
+                    .flat_map(|&b| [b & 0x0F, b >> 4]);
+                let count = 2 * (data.len() - ADPCM_HEADER_BYTES) - padded as usize;
+                Ok(nibbles
+                    .take(count)
+                    .flat_map(|nibble| state.decode(nibble).to_le_bytes())
+                    .collect())
+            }
+        }
+    }
+}
+
+/// Stateful encoder for one contiguous stream of chunks
+#[derive(Debug, Clone)]
+pub struct AudioEncoder {
+    codec: AudioCodec,
+    adpcm: AdpcmState,
+}
+
+impl AudioEncoder {
+    pub fn new(codec: AudioCodec) -> Self {
+        Self {
+            codec,
+            adpcm: AdpcmState::default(),
+        }
+    }
+
+    /// Encode one s16le chunk. A trailing odd byte is ignored.
+    pub fn encode(&mut self, s16le: &[u8]) -> Vec<u8> {
+        let samples = s16le
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]));
+        match self.codec {
+            AudioCodec::Raw => s16le.to_vec(),
+            AudioCodec::Ulaw => samples.map(ulaw_encode).collect(),
+            AudioCodec::ImaAdpcm => {
+                let count = s16le.len() / 2;
+                let mut out = Vec::with_capacity(ADPCM_HEADER_BYTES + count.div_ceil(2));
+                out.extend_from_slice(&(self.adpcm.predictor as i16).to_le_bytes());
+                out.push(self.adpcm.index as u8);
+                out.push((count % 2) as u8);
+
+                let mut low: Option<u8> = None;
+                for sample in samples {
+                    let nibble = self.adpcm.encode(sample);
+                    match low.take() {
+                        Some(low) => out.push(low | nibble << 4),
+                        None => low = Some(nibble),
+                    }
+                }
+                out.extend(low);
+                out
+            }
+        }
+    }
+}
+
+const ADPCM_HEADER_BYTES: usize = 4;
+
+const INDEX_TABLE: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// IMA ADPCM predictor, shared by the encoder and decoder so both
+/// reconstruct the same samples
+#[derive(Debug, Clone, Copy, Default)]
+struct AdpcmState {
+    predictor: i32,
+    index: usize,
+}
+
+impl AdpcmState {
+    fn encode(&mut self, sample: i16) -> u8 {
+        let mut diff = sample as i32 - self.predictor;
+        let mut nibble = 0u8;
+        if diff < 0 {
+            nibble = 8;
+            diff = -diff;
+        }
+
+        let mut step = STEP_TABLE[self.index];
+        for bit in [4, 2, 1] {
+            if diff >= step {
+                nibble |= bit;
+                diff -= step;
+            }
+            step >>= 1;
+        }
+
+        self.decode(nibble);
+        nibble
+    }
+
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = STEP_TABLE[self.index];
+        let mut delta = step >> 3;
+        if nibble & 4 != 0 {
+            delta += step;
+        }
+        if nibble & 2 != 0 {
+            delta += step >> 1;
+        }
+        if nibble & 1 != 0 {
+            delta += step >> 2;
+        }
+        if nibble & 8 != 0 {
+            delta = -delta;
+        }
+
+        self.predictor = (self.predictor + delta).clamp(i16::MIN as i32, i16::MAX as i32);
+        self.index = (self.index as i32 + INDEX_TABLE[(nibble & 7) as usize])
+            .clamp(0, STEP_TABLE.len() as i32 - 1) as usize;
+        self.predictor as i16
+    }
+}
+
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 32635;
+
+fn ulaw_encode(sample: i16) -> u8 {
+    let mut magnitude = sample as i32;
+    let sign = if magnitude < 0 {
+        magnitude = -magnitude;
+        0x80
+    } else {
+        0
+    };
+    let biased = magnitude.min(ULAW_CLIP) + ULAW_BIAS;
+
+    // Segment of the highest set bit above the 8-bit floor
+    let exponent = (31 - biased.leading_zeros() as i32 - 7).clamp(0, 7);
+    let mantissa = (biased >> (exponent + 3)) & 0x0F;
+    !(sign | (exponent << 4) as u8 | mantissa as u8)
+}
+
+fn ulaw_decode(byte: u8) -> i16 {
+    let byte = !byte;
+    let exponent = ((byte >> 4) & 0x07) as i32;
+    let mantissa = (byte & 0x0F) as i32;
+    let magnitude = (((mantissa << 3) + ULAW_BIAS) << exponent) - ULAW_BIAS;
+    if byte & 0x80 != 0 {
+        -magnitude as i16
+    } else {
+        magnitude as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speech_like(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / 16000.0;
+                let x = 6000.0 * (2.0 * std::f64::consts::PI * 220.0 * t).sin()
+                    + 2000.0 * (2.0 * std::f64::consts::PI * 1700.0 * t).sin();
+                x as i16
+            })
+            .flat_map(i16::to_le_bytes)
+            .collect()
+    }
+
+    /// Signal-to-noise ratio of `decoded` against `original`, in dB
+    fn snr(original: &[u8], decoded: &[u8]) -> f64 {
+        let samples = |b: &[u8]| -> Vec<f64> {
+            b.chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64)
+                .collect()
+        };
+        let (original, decoded) = (samples(original), samples(decoded));
+        assert_eq!(original.len(), decoded.len());
+        let signal: f64 = original.iter().map(|x| x * x).sum();
+        let noise: f64 = original
+            .iter()
+            .zip(&decoded)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum();
+        10.0 * (signal / noise).log10()
+    }
+
+    #[test]
+    fn test_ulaw_round_trip() {
+        for sample in [0i16, 1, -1, 100, -100, 1000, -5000, 32767, -32768] {
+            let decoded = ulaw_decode(ulaw_encode(sample)) as i32;
+            // Segment steps grow with magnitude; the error stays within half a step
+            let tolerance = (sample as i32).abs() / 16 + 4;
+            assert!(
+                (decoded - (sample as i32).clamp(-ULAW_CLIP, ULAW_CLIP)).abs() <= tolerance,
+                "{} -> {}",
+                sample,
+                decoded
+            );
+        }
+
+        let audio = speech_like(1280);
+        let mut encoder = AudioEncoder::new(AudioCodec::Ulaw);
+        let encoded = encoder.encode(&audio);
+        assert_eq!(encoded.len(), 1280);
+        assert!(snr(&audio, &AudioCodec::Ulaw.decode(&encoded).unwrap()) > 30.0);
+    }
+
+    #[test]
+    fn test_adpcm_chunks_decode_independently() {
+        let audio = speech_like(1280 * 4);
+        let mut encoder = AudioEncoder::new(AudioCodec::ImaAdpcm);
+        let chunks: Vec<Vec<u8>> = audio.chunks(2560).map(|c| encoder.encode(c)).collect();
+        assert!(chunks.iter().all(|c| c.len() == ADPCM_HEADER_BYTES + 640));
+
+        // Skipping a chunk, as a lagging consumer might, loses nothing else
+        let decoded: Vec<u8> = chunks
+            .iter()
+            .skip(1)
+            .flat_map(|c| AudioCodec::ImaAdpcm.decode(c).unwrap())
+            .collect();
+        assert!(snr(&audio[2560..], &decoded) > 20.0);
+    }
+
+    #[test]
+    fn test_adpcm_odd_length_and_truncation() {
+        let audio = speech_like(101);
+        let mut encoder = AudioEncoder::new(AudioCodec::ImaAdpcm);
+        let encoded = encoder.encode(&audio);
+        assert_eq!(encoded.len(), ADPCM_HEADER_BYTES + 51);
+        assert_eq!(AudioCodec::ImaAdpcm.decode(&encoded).unwrap().len(), 202);
+
+        assert!(AudioCodec::ImaAdpcm.decode(&encoded[..3]).is_err());
+        // A padding flag with no sample bytes is malformed, not a panic
+        assert!(AudioCodec::ImaAdpcm.decode(&[0, 0, 0, 1]).is_err());
+        assert!(AudioCodec::ImaAdpcm
+            .decode(&[0, 0, 0, 0])
+            .unwrap()
+            .is_empty());
+        assert_eq!(AudioCodec::Raw.decode(&audio).unwrap(), audio);
+    }
+}
@@ -1,7 +1,8 @@
+use crate::resample::{ResampleQuality, Resampler};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{
-    BuildStreamError, DeviceNameError, DevicesError, PlayStreamError, SampleFormat, Stream,
-    SupportedStreamConfigsError,
+    BuildStreamError, DeviceNameError, DevicesError, FromSample, PlayStreamError, Sample,
+    SampleFormat, SizedSample, Stream, SupportedStreamConfigsError,
 };
 use crossbeam::channel::{bounded, Receiver, Sender};
 use ringbuf::{
@@ -16,13 +17,13 @@ use thiserror::Error;
 
 pub use crate::types::AudioDeviceInfo;
 
-const TARGET_SAMPLE_RATE: u32 = 48000;
+/// Rate of the audio written to the sink; devices at other rates are resampled
+pub const TARGET_SAMPLE_RATE: u32 = 48000;
 const TARGET_CHANNELS: u16 = 1;
-const RING_CAPACITY: usize = 240_000; // 5 seconds at 48kHz
-const MAX_CALLBACK_FRAMES: usize = 8192;
+const RING_CAPACITY_MS: usize = 5000;
 /// Silence to pre-fill the ring buffer before stream.play() so the first
 /// ALSA period has data and doesn't immediately underrun.
-const PREFILL_SILENCE_SAMPLES: usize = 9600; // 200ms at 48kHz
+const PREFILL_SILENCE_MS: usize = 200;
 
 /// Minimum number of buffered samples before we start CONSUMING from the
 /// ring on a new stream. The cpal callback emits silence while priming
@@ -35,13 +36,12 @@ const STREAM_PRIME_DEFAULT_MS: u64 = 500;
 const STREAM_PRIME_MIN_MS: u64 = 50;
 const STREAM_PRIME_MAX_MS: u64 = 2000;
 
-fn stream_prime_samples() -> usize {
-    let ms = std::env::var("AUDIO_PRIME_MS")
+fn stream_prime_ms() -> usize {
+    std::env::var("AUDIO_PRIME_MS")
         .ok()
         .and_then(|s| s.parse::<u64>().ok())
         .unwrap_or(STREAM_PRIME_DEFAULT_MS)
-        .clamp(STREAM_PRIME_MIN_MS, STREAM_PRIME_MAX_MS);
-    (ms as usize) * (TARGET_SAMPLE_RATE as usize) / 1000
+        .clamp(STREAM_PRIME_MIN_MS, STREAM_PRIME_MAX_MS) as usize
 }
 
 #[derive(Error, Debug, Clone)]
@@ -89,11 +89,16 @@ impl From<DeviceNameError> for AudioError {
 #[derive(Clone)]
 pub struct AudioSinkConfig {
     pub device_name: Option<String>,
+    /// Resampler used when the device doesn't run at 48kHz
+    pub resample_quality: ResampleQuality,
 }
 
 impl Default for AudioSinkConfig {
     fn default() -> Self {
-        Self { device_name: None }
+        Self {
+            device_name: None,
+            resample_quality: ResampleQuality::default(),
+        }
     }
 }
 
@@ -144,26 +149,85 @@ fn atomic_max_u64(slot: &AtomicU64, val: u64) {
     }
 }
 
-/// Decode s16le bytes and push into the SPSC ring buffer (command thread only).
-/// Spins with short sleeps when the ring is full so we never silently drop samples.
-/// Returns the number of i16 samples pushed.
-fn push_s16le_to_ring(prod: &mut HeapProd<i16>, s16le_data: &[u8]) -> Result<usize, AudioError> {
-    if s16le_data.len() % 2 != 0 {
-        return Err(AudioError::WriteError(
-            "S16LE data length not aligned to 16-bit samples".to_string(),
-        ));
+/// Turns mono 48kHz s16le chunks into interleaved frames in the device's
+/// sample format, rate and channel count (command thread only). Buffers are
+/// reused across chunks, so the callback only has to copy.
+struct SinkConverter<T> {
+    resampler: Resampler,
+    channels: usize,
+    decoded: Vec<f32>,
+    resampled: Vec<f32>,
+    frames: Vec<T>,
+}
+
+impl<T> SinkConverter<T>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    fn new(device_rate: u32, channels: usize, quality: ResampleQuality) -> Self {
+        Self {
+            resampler: Resampler::new(TARGET_SAMPLE_RATE, device_rate, quality),
+            channels,
+            decoded: Vec::new(),
+            resampled: Vec::new(),
+            frames: Vec::new(),
+        }
     }
-    let samples: Vec<i16> = s16le_data
-        .chunks_exact(2)
-        .map(|c| i16::from_le_bytes([c[0], c[1]]))
-        .collect();
 
+    /// Decode, resample and fan out one s16le chunk
+    fn convert(&mut self, s16le_data: &[u8]) -> Result<&[T], AudioError> {
+        if !s16le_data.len().is_multiple_of(2) {
+            return Err(AudioError::WriteError(
+                "S16LE data length not aligned to 16-bit samples".to_string(),
+            ));
+        }
+        self.decoded.clear();
+        self.decoded.extend(
+            s16le_data
+                .chunks_exact(2)
+                .map(|c| i16::from_le_bytes([c[0], c[1]]).to_sample::<f32>()),
+        );
+
+        self.resampled.clear();
+        self.resampler.process(&self.decoded, &mut self.resampled);
+        Ok(self.fan_out())
+    }
+
+    /// Frames the resampler still holds back at the end of a stream
+    fn flush(&mut self) -> &[T] {
+        self.resampled.clear();
+        self.resampler.flush(&mut self.resampled);
+        self.fan_out()
+    }
+
+    /// Drop buffered audio of an interrupted stream
+    fn reset(&mut self) {
+        self.resampler.reset();
+    }
+
+    fn fan_out(&mut self) -> &[T] {
+        self.frames.clear();
+        for &sample in &self.resampled {
+            let sample = sample.to_sample::<T>();
+            self.frames
+                .extend(std::iter::repeat_n(sample, self.channels));
+        }
+        &self.frames
+    }
+}
+
+/// Push interleaved frames into the SPSC ring buffer (command thread only).
+/// Spins with short sleeps when the ring is full so we never silently drop
+/// samples, and only pushes whole frames so channels stay aligned.
+/// Returns the number of samples pushed.
+fn push_to_ring<T: Copy>(prod: &mut HeapProd<T>, samples: &[T], channels: usize) -> usize {
     let total = samples.len();
     let mut offset = 0;
     let mut spins = 0u32;
     while offset < total {
-        let pushed = prod.push_slice(&samples[offset..]);
-        offset += pushed;
+        let room = prod.vacant_len() / channels * channels;
+        let end = total.min(offset + room);
+        offset += prod.push_slice(&samples[offset..end]);
         if offset < total {
             spins += 1;
             thread::sleep(Duration::from_millis(1));
@@ -171,16 +235,17 @@ fn push_s16le_to_ring(prod: &mut HeapProd<i16>, s16le_data: &[u8]) -> Result<usi
     }
     if spins > 0 {
         log::debug!(
-            "🔁 push_s16le_to_ring: ring was full, spun {}ms to push {} samples",
+            "🔁 push_to_ring: ring was full, spun {}ms to push {} samples",
             spins,
             total
         );
     }
-    Ok(total)
+    total
 }
 
 /// Sync streaming audio sink.
-/// Accepts mono 48kHz s16le and feeds directly to I16 ALSA hardware.
+/// Accepts mono 48kHz s16le and converts it to whatever sample format, rate
+/// and channel count the output device runs at.
 pub struct AudioSink {
     command_tx: Sender<AudioCommand>,
     _handle: thread::JoinHandle<()>,
@@ -321,12 +386,67 @@ impl AudioSink {
         let hardware_channels = stream_config.channels;
 
         log::info!(
-            "🔊 Output: {}Hz, {}ch, {:?}",
+            "🔊 Output: {}Hz, {}ch, {:?}{}",
             hardware_sample_rate,
             hardware_channels,
             supported_config.sample_format(),
+            if hardware_sample_rate == TARGET_SAMPLE_RATE {
+                String::new()
+            } else {
+                format!(" ({} resampling)", config.resample_quality)
+            }
         );
 
+        macro_rules! run {
+            ($sample:ty) => {
+                Self::run_stream::<$sample>(
+                    &device,
+                    &stream_config,
+                    config.resample_quality,
+                    command_rx,
+                    ready_tx,
+                )
+            };
+        }
+        match supported_config.sample_format() {
+            SampleFormat::I8 => run!(i8),
+            SampleFormat::I16 => run!(i16),
+            SampleFormat::I24 => run!(cpal::I24),
+            SampleFormat::I32 => run!(i32),
+            SampleFormat::I64 => run!(i64),
+            SampleFormat::U8 => run!(u8),
+            SampleFormat::U16 => run!(u16),
+            SampleFormat::U32 => run!(u32),
+            SampleFormat::U64 => run!(u64),
+            SampleFormat::F32 => run!(f32),
+            SampleFormat::F64 => run!(f64),
+            other => bail!(AudioError::DeviceError(format!(
+                "Unsupported output sample format {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Play through an output stream of sample type `T`, feeding it from
+    /// `command_rx` until the sink is dropped
+    fn run_stream<T>(
+        device: &cpal::Device,
+        stream_config: &cpal::StreamConfig,
+        resample_quality: ResampleQuality,
+        command_rx: Receiver<AudioCommand>,
+        ready_tx: mpsc::SyncSender<Result<(), AudioError>>,
+    ) -> Result<(), AudioError>
+    where
+        T: SizedSample + FromSample<f32> + Send + 'static,
+    {
+        let channels = stream_config.channels as usize;
+        // Ring samples per second of audio
+        let ring_rate = stream_config.sample_rate.0 as usize * channels;
+        let ring_capacity = RING_CAPACITY_MS * ring_rate / 1000;
+        let prefill_silence = PREFILL_SILENCE_MS * ring_rate / 1000 / channels * channels;
+        let mut converter =
+            SinkConverter::<T>::new(stream_config.sample_rate.0, channels, resample_quality);
+
         let underrun_count = Arc::new(AtomicU64::new(0));
         let stream_broken = Arc::new(AtomicBool::new(false));
         let clear_flag = Arc::new(AtomicBool::new(false));
@@ -347,10 +467,10 @@ impl AudioSink {
         let cb_silent_pad_count = Arc::new(AtomicU64::new(0));
         let cb_silent_pad_samples = Arc::new(AtomicU64::new(0));
 
-        let rb = HeapRb::<i16>::new(RING_CAPACITY);
+        let rb = HeapRb::<T>::new(ring_capacity);
         let (mut prod, cons) = rb.split();
 
-        let silence = vec![0i16; PREFILL_SILENCE_SAMPLES];
+        let silence = vec![T::EQUILIBRIUM; prefill_silence];
         prod.push_slice(&silence);
 
         let stream_diag = StreamDiagnostics {
@@ -368,9 +488,9 @@ impl AudioSink {
             cb_silent_pad_samples: Arc::clone(&cb_silent_pad_samples),
         };
 
-        let mut stream = Self::build_i16_stream(
-            &device,
-            &stream_config,
+        let mut stream = Self::build_stream(
+            device,
+            stream_config,
             cons,
             Arc::clone(&underrun_count),
             Arc::clone(&stream_broken),
@@ -401,11 +521,11 @@ impl AudioSink {
         let mut stream_total_bytes: u64 = 0;
         let mut stream_total_samples: u64 = 0;
         let mut stream_start_time: Option<Instant> = None;
-        let prime_target = stream_prime_samples();
+        let prime_target = stream_prime_ms() * ring_rate / 1000;
         log::info!(
             "🎯 Stream prime watermark: {} samples (~{}ms)",
             prime_target,
-            prime_target * 1000 / TARGET_SAMPLE_RATE as usize
+            prime_target * 1000 / ring_rate
         );
 
         loop {
@@ -415,18 +535,18 @@ impl AudioSink {
                 log::warn!("CPAL stream broken (xrun), recreating...");
                 drop(stream);
 
-                let rb = HeapRb::<i16>::new(RING_CAPACITY);
+                let rb = HeapRb::<T>::new(ring_capacity);
                 let (new_prod, new_cons) = rb.split();
                 prod = new_prod;
 
-                let silence = vec![0i16; PREFILL_SILENCE_SAMPLES];
+                let silence = vec![T::EQUILIBRIUM; prefill_silence];
                 prod.push_slice(&silence);
 
                 stream_broken.store(false, Ordering::Release);
 
-                match Self::build_i16_stream(
-                    &device,
-                    &stream_config,
+                match Self::build_stream(
+                    device,
+                    stream_config,
                     new_cons,
                     Arc::clone(&underrun_count),
                     Arc::clone(&stream_broken),
@@ -556,6 +676,7 @@ impl AudioSink {
                                     log::info!("🆕 First stream started: {}", stream_id);
                                 }
                                 current_stream_id = stream_id;
+                                converter.reset();
                                 stream_chunk_count = 0;
                                 stream_total_bytes = 0;
                                 stream_total_samples = 0;
@@ -571,7 +692,7 @@ impl AudioSink {
                             }
 
                             let pushed =
-                                push_s16le_to_ring(&mut prod, &s16le_data)?;
+                                push_to_ring(&mut prod, converter.convert(&s16le_data)?, channels);
                             stream_chunk_count += 1;
                             stream_total_bytes += s16le_data.len() as u64;
                             stream_total_samples += pushed as u64;
@@ -588,7 +709,7 @@ impl AudioSink {
                             // Push chunks that were queued behind the current one (in order).
                             for chunk in saved_new_chunks.drain(..) {
                                 let pushed2 =
-                                    push_s16le_to_ring(&mut prod, &chunk)?;
+                                    push_to_ring(&mut prod, converter.convert(&chunk)?, channels);
                                 stream_chunk_count += 1;
                                 stream_total_bytes += chunk.len() as u64;
                                 stream_total_samples += pushed2 as u64;
@@ -609,7 +730,7 @@ impl AudioSink {
                                     "🎬 Stream {} primed: {} samples buffered (~{}ms audio), priming took {}ms wall",
                                     current_stream_id,
                                     buffered,
-                                    buffered * 1000 / TARGET_SAMPLE_RATE as usize,
+                                    buffered * 1000 / ring_rate,
                                     prime_ms
                                 );
                                 playback_active.store(true, Ordering::Release);
@@ -632,7 +753,7 @@ impl AudioSink {
                                         stream_total_bytes,
                                         stream_total_samples,
                                         consumed,
-                                        stream_total_samples as f64 / TARGET_SAMPLE_RATE as f64,
+                                        stream_total_samples as f64 / ring_rate as f64,
                                         elapsed
                                     );
                                     playback_active.store(false, Ordering::Release);
@@ -644,6 +765,9 @@ impl AudioSink {
                             }
                         }
                         AudioCommand::EndStreamAndWait(tx) => {
+                            // Play out what the resampler was holding back
+                            stream_total_samples +=
+                                push_to_ring(&mut prod, converter.flush(), channels) as u64;
                             let ring_occ = prod.occupied_len();
                             log::info!(
                                 "🏁 EndStreamAndWait for stream {}: {} chunks, {} samples pushed, ring={}, cb_consumed={}",
@@ -662,7 +786,7 @@ impl AudioSink {
                                     "🎬 Stream {} ending below prime watermark, releasing playback with {} samples (~{}ms)",
                                     current_stream_id,
                                     ring_occ,
-                                    ring_occ * 1000 / TARGET_SAMPLE_RATE as usize
+                                    ring_occ * 1000 / ring_rate
                                 );
                                 playback_active.store(true, Ordering::Release);
                             }
@@ -691,6 +815,7 @@ impl AudioSink {
 
                             clear_flag.store(true, Ordering::Release);
                             playback_active.store(false, Ordering::Release);
+                            converter.reset();
 
                             current_stream_id = 0;
 
@@ -718,7 +843,7 @@ impl AudioSink {
                                 stream_total_bytes,
                                 stream_total_samples,
                                 consumed,
-                                stream_total_samples as f64 / TARGET_SAMPLE_RATE as f64,
+                                stream_total_samples as f64 / ring_rate as f64,
                                 elapsed
                             );
                             playback_active.store(false, Ordering::Release);
@@ -738,7 +863,8 @@ impl AudioSink {
         Ok(())
     }
 
-    /// Find best I16 output config at 48kHz, preferring mono but accepting stereo.
+    /// Find the output config needing the least conversion: 48kHz, I16 and
+    /// mono preferred, anything else converted by `SinkConverter`.
     fn select_output_config(
         device: &cpal::Device,
     ) -> Result<cpal::SupportedStreamConfig, AudioError> {
@@ -755,13 +881,13 @@ impl AudioSink {
             } else if range.channels() == 2 {
                 10
             } else {
-                continue;
+                20
             };
 
-            let format_penalty: u32 = if range.sample_format() == SampleFormat::I16 {
-                0
-            } else {
-                100
+            let format_penalty: u32 = match range.sample_format() {
+                SampleFormat::I16 => 0,
+                SampleFormat::I24 | SampleFormat::I32 | SampleFormat::F32 => 50,
+                _ => 100,
             };
 
             let min = range.min_sample_rate().0;
@@ -782,11 +908,13 @@ impl AudioSink {
     }
 
     /// Build the CPAL output stream with a lock-free ring buffer consumer.
-    /// The callback is allocation-free and mutex-free.
-    fn build_i16_stream(
+    /// The ring already holds frames in the device's format, so the callback
+    /// only copies; it is allocation-free and mutex-free.
+    #[allow(clippy::too_many_arguments)]
+    fn build_stream<T>(
         device: &cpal::Device,
         config: &cpal::StreamConfig,
-        mut consumer: HeapCons<i16>,
+        mut consumer: HeapCons<T>,
         underrun_count: Arc<AtomicU64>,
         stream_broken: Arc<AtomicBool>,
         clear_flag: Arc<AtomicBool>,
@@ -794,7 +922,10 @@ impl AudioSink {
         callback_samples_consumed: Arc<AtomicU64>,
         callback_buf_size: Arc<AtomicU64>,
         diag: StreamDiagnostics,
-    ) -> Result<Stream, AudioError> {
+    ) -> Result<Stream, AudioError>
+    where
+        T: SizedSample + Send + 'static,
+    {
         let channels = config.channels as usize;
         let rt_set = Arc::new(AtomicBool::new(false));
         // Per-callback timing state (only touched by the callback thread).
@@ -802,7 +933,7 @@ impl AudioSink {
         device
             .build_output_stream(
                 config,
-                move |data: &mut [i16], info: &cpal::OutputCallbackInfo| {
+                move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
                     let cb_entry = Instant::now();
 
                     #[cfg(target_os = "linux")]
@@ -865,9 +996,7 @@ impl AudioSink {
                     if clear_flag.load(Ordering::Acquire) {
                         consumer.clear();
                         clear_flag.store(false, Ordering::Release);
-                        for s in data.iter_mut() {
-                            *s = 0;
-                        }
+                        data.fill(T::EQUILIBRIUM);
                         let work_us = cb_entry.elapsed().as_micros() as u64;
                         atomic_max_u64(&diag.cb_work_max_us, work_us);
                         return;
@@ -882,57 +1011,24 @@ impl AudioSink {
                     // loop flips playback_active to true when the watermark is
                     // reached or the stream ends.
                     if !playback_active.load(Ordering::Relaxed) {
-                        for s in data.iter_mut() {
-                            *s = 0;
-                        }
+                        data.fill(T::EQUILIBRIUM);
                         let work_us = cb_entry.elapsed().as_micros() as u64;
                         atomic_max_u64(&diag.cb_work_max_us, work_us);
                         return;
                     }
 
-                    if channels == 1 {
-                        let popped = consumer.pop_slice(data);
-                        if popped > 0 {
-                            callback_samples_consumed
-                                .fetch_add(popped as u64, Ordering::Relaxed);
-                        }
-                        if popped < data.len() {
-                            let pad = (data.len() - popped) as u64;
-                            for s in data[popped..].iter_mut() {
-                                *s = 0;
-                            }
-                            if playback_active.load(Ordering::Relaxed) {
-                                underrun_count.fetch_add(1, Ordering::Relaxed);
-                                diag.cb_silent_pad_count.fetch_add(1, Ordering::Relaxed);
-                                diag.cb_silent_pad_samples
-                                    .fetch_add(pad, Ordering::Relaxed);
-                            }
-                        }
-                    } else {
-                        let frames = data.len() / channels;
-                        let mut mono_buf = [0i16; MAX_CALLBACK_FRAMES];
-                        let to_pop = frames.min(MAX_CALLBACK_FRAMES);
-                        let popped = consumer.pop_slice(&mut mono_buf[..to_pop]);
-                        if popped > 0 {
-                            callback_samples_consumed
-                                .fetch_add(popped as u64, Ordering::Relaxed);
-                        }
-                        for i in 0..popped {
-                            for ch in 0..channels {
-                                data[i * channels + ch] = mono_buf[i];
-                            }
-                        }
-                        if popped < frames {
-                            let pad = (frames - popped) as u64;
-                            for s in data[popped * channels..].iter_mut() {
-                                *s = 0;
-                            }
-                            if playback_active.load(Ordering::Relaxed) {
-                                underrun_count.fetch_add(1, Ordering::Relaxed);
-                                diag.cb_silent_pad_count.fetch_add(1, Ordering::Relaxed);
-                                diag.cb_silent_pad_samples
-                                    .fetch_add(pad, Ordering::Relaxed);
-                            }
+                    let popped = consumer.pop_slice(data);
+                    if popped > 0 {
+                        callback_samples_consumed.fetch_add(popped as u64, Ordering::Relaxed);
+                    }
+                    if popped < data.len() {
+                        // Padding in frames, i.e. samples of the mono input
+                        let pad = ((data.len() - popped) / channels) as u64;
+                        data[popped..].fill(T::EQUILIBRIUM);
+                        if playback_active.load(Ordering::Relaxed) {
+                            underrun_count.fetch_add(1, Ordering::Relaxed);
+                            diag.cb_silent_pad_count.fetch_add(1, Ordering::Relaxed);
+                            diag.cb_silent_pad_samples.fetch_add(pad, Ordering::Relaxed);
                         }
                     }
 
@@ -998,7 +1094,9 @@ mod tests {
 
         let rb = HeapRb::<i16>::new(64);
         let (mut prod, mut cons) = rb.split();
-        push_s16le_to_ring(&mut prod, &s16le_data).unwrap();
+        let mut converter =
+            SinkConverter::<i16>::new(TARGET_SAMPLE_RATE, 1, ResampleQuality::default());
+        push_to_ring(&mut prod, converter.convert(&s16le_data).unwrap(), 1);
 
         assert_eq!(prod.occupied_len(), 4);
 
@@ -1021,4 +1119,52 @@ mod tests {
         assert_eq!(out[..3], [1, 2, 3]);
         assert_eq!(prod.occupied_len(), 0);
     }
+
+    #[test]
+    fn test_converter_resamples_and_fans_out() {
+        let mut converter = SinkConverter::<f32>::new(44100, 2, ResampleQuality::default());
+        assert!(converter.convert(&[0x00]).is_err());
+
+        // 1s of a 440Hz tone in 20ms chunks
+        let tone: Vec<u8> = (0..TARGET_SAMPLE_RATE)
+            .flat_map(|n| {
+                let t = n as f32 / TARGET_SAMPLE_RATE as f32;
+                let sample = (8192.0 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()) as i16;
+                sample.to_le_bytes()
+            })
+            .collect();
+        let mut frames = Vec::new();
+        for chunk in tone.chunks(1920) {
+            frames.extend_from_slice(converter.convert(chunk).unwrap());
+        }
+        frames.extend_from_slice(converter.flush());
+
+        assert_eq!(frames.len(), 2 * 44100);
+        for (n, frame) in frames.chunks_exact(2).enumerate().skip(100) {
+            assert_eq!(frame[0], frame[1]);
+            let t = n as f32 / 44100.0;
+            let ideal = 0.25 * (2.0 * std::f32::consts::PI * 440.0 * t).sin();
+            assert!((frame[0] - ideal).abs() < 0.005, "{}: {}", n, frame[0]);
+        }
+    }
+
+    #[test]
+    fn test_ring_push_keeps_frames_whole() {
+        let rb = HeapRb::<i16>::new(5);
+        let (mut prod, mut cons) = rb.split();
+        let reader = thread::spawn(move || {
+            let mut out = Vec::new();
+            while out.len() < 8 {
+                // Pops only ever see whole stereo frames
+                assert_eq!(cons.occupied_len() % 2, 0);
+                let mut buf = [0i16; 5];
+                let n = cons.pop_slice(&mut buf);
+                out.extend_from_slice(&buf[..n]);
+                thread::sleep(Duration::from_millis(1));
+            }
+            out
+        });
+        assert_eq!(push_to_ring(&mut prod, &[1, 1, 2, 2, 3, 3, 4, 4], 2), 8);
+        assert_eq!(reader.join().unwrap(), [1, 1, 2, 2, 3, 3, 4, 4]);
+    }
 }
@@ -1,9 +1,10 @@
+use crate::resample::{ResampleQuality, Resampler};
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    Device, SampleFormat, Stream as CpalStream,
+    Device, FromSample, Sample, SampleFormat, SizedSample, Stream as CpalStream,
 };
 use crossbeam::channel::{bounded, Receiver, Sender};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -15,6 +16,21 @@ use std::time::Instant;
 
 pub const CHUNK_SIZE: usize = 1280; // Fixed chunk size (in samples)
 
+/// Rate of the delivered chunks; hardware at other rates is resampled
+pub const OUTPUT_SAMPLE_RATE: u32 = 16000;
+
+/// How long a running stream may deliver no chunk before its device counts
+/// as lost; some drivers stop calling back on unplug instead of erroring
+const STALL_TIMEOUT: Duration = Duration::from_secs(2);
+/// Wait before reopening a broken stream, doubled after each failed attempt
+const REOPEN_BACKOFF_MIN: Duration = Duration::from_millis(500);
+const REOPEN_BACKOFF_MAX: Duration = Duration::from_secs(10);
+/// How often `CaptureStatus::Lost` is repeated while capture stays down
+const LOST_NOTICE_INTERVAL: Duration = Duration::from_secs(30);
+/// How often capture running on the default input looks for the configured
+/// device to come back
+const PREFERRED_DEVICE_RECHECK: Duration = Duration::from_secs(10);
+
 #[derive(Error, Debug)]
 pub enum AudioCaptureError {
     #[error("No audio devices found")]
@@ -38,6 +54,14 @@ pub struct AudioCaptureConfig {
     /// raise the level of low-output mics (e.g. the Jabra USB speakerphone)
     /// into the range openWakeWord expects. Applied with hard clamping.
     pub gain: f32,
+    /// Also deliver every device channel in `CapturedChunk::channels`, e.g. to
+    /// score several mics of an array independently
+    pub all_channels: bool,
+    /// Other channels the device must have, e.g. the ones scored for wake
+    /// words. A config without them is never opened.
+    pub required_channels: Vec<u32>,
+    /// Resampler used when the device doesn't run at 16kHz
+    pub resample_quality: ResampleQuality,
 }
 
 impl Default for AudioCaptureConfig {
@@ -46,14 +70,44 @@ impl Default for AudioCaptureConfig {
             device_id: None,
             channel: 0,
             gain: 1.0,
+            all_channels: false,
+            required_channels: Vec::new(),
+            resample_quality: ResampleQuality::default(),
         }
     }
 }
 
+/// One captured chunk of mono 16kHz s16le audio
+#[derive(Debug, Clone)]
+pub struct CapturedChunk {
+    pub data: Vec<u8>,
+    /// Index of the chunk's first sample since capture started. Chunks dropped
+    /// because the reader fell behind still advance it, so it stays aligned
+    /// with the audio timeline.
+    pub sample_index: u64,
+    /// The same span of audio for every device channel (gain applied), indexed
+    /// by channel. Empty unless `AudioCaptureConfig::all_channels` is set.
+    pub channels: Vec<Vec<i16>>,
+}
+
+/// Changes in whether the capture device is delivering audio
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureStatus {
+    /// The device failed or disappeared and could not be reopened yet; no
+    /// chunks arrive until `Restored`. Repeated while retrying.
+    Lost(String),
+    /// A stream is running again after `deaf_for` without audio
+    Restored { deaf_for: Duration },
+}
+
 /// Sync audio capture that outputs mono 16kHz s16le chunks.
-/// Assumes hardware delivers I16 at 16kHz (XVF3800).
+/// Accepts any sample format, rate and channel count from the hardware and
+/// converts internally, so no ALSA `plug` device is needed in front of it.
+/// A lost device (e.g. an unplugged USB mic) is reopened with backoff, or
+/// replaced by the default input until it returns, and the chunk stream resumes.
 pub struct AudioCapture {
-    receiver: Receiver<Vec<u8>>,
+    receiver: Receiver<CapturedChunk>,
+    status_receiver: Receiver<CaptureStatus>,
     stop_sender: Sender<()>,
     _handle: thread::JoinHandle<()>,
 }
@@ -61,18 +115,18 @@ pub struct AudioCapture {
 impl AudioCapture {
     pub fn new(config: AudioCaptureConfig) -> Result<Self, AudioCaptureError> {
         let (sender, receiver) = bounded(15);
+        let (status_sender, status_receiver) = bounded(8);
         let (stop_sender, stop_receiver) = bounded(1);
 
         let handle = thread::spawn(move || {
-            if let Err(e) = Self::run_capture_thread(config, sender, stop_receiver) {
-                log::error!("Audio capture thread failed: {}", e);
-            }
+            Self::run_capture_thread(config, sender, status_sender, stop_receiver);
         });
 
         thread::sleep(Duration::from_millis(50));
 
         Ok(Self {
             receiver,
+            status_receiver,
             stop_sender,
             _handle: handle,
         })
@@ -82,55 +136,50 @@ impl AudioCapture {
         let _ = self.stop_sender.send(());
     }
 
-    /// Get the next audio chunk as s16le bytes (blocking).
-    pub fn next_chunk(&self) -> Option<Vec<u8>> {
+    /// Get the next audio chunk (blocking).
+    pub fn next_chunk(&self) -> Option<CapturedChunk> {
         self.receiver.recv().ok()
     }
 
     /// Try to get the next audio chunk without blocking.
-    pub fn try_next_chunk(&self) -> Option<Vec<u8>> {
+    pub fn try_next_chunk(&self) -> Option<CapturedChunk> {
         self.receiver.try_recv().ok()
     }
 
+    /// Try to get the next device status change without blocking.
+    pub fn try_next_status(&self) -> Option<CaptureStatus> {
+        self.status_receiver.try_recv().ok()
+    }
+
     fn run_capture_thread(
         config: AudioCaptureConfig,
-        sender: Sender<Vec<u8>>,
+        sender: Sender<CapturedChunk>,
+        status_sender: Sender<CaptureStatus>,
         stop_receiver: Receiver<()>,
-    ) -> Result<(), AudioCaptureError> {
+    ) {
         let host = cpal::default_host();
         log::info!("🎤 Initializing audio capture with host: {:?}", host.id());
 
-        let device = if let Some(id) = &config.device_id {
-            if id == "default" {
-                host.default_input_device().ok_or_else(|| {
-                    AudioCaptureError::Device("No default input device found".into())
-                })?
-            } else {
-                host.devices()
-                    .map_err(|e| AudioCaptureError::Device(e.to_string()))?
-                    .find(|d| d.name().map(|n| n == *id).unwrap_or(false))
-                    .ok_or_else(|| {
-                        AudioCaptureError::Device(format!("Device not found: {}", id))
-                    })?
-            }
-        } else {
-            host.default_input_device()
-                .ok_or_else(|| AudioCaptureError::Device("No default input device found".into()))?
-        };
-
-        log::info!("🎤 Using input device: {:?}", device.name());
-
         let stream_broken = Arc::new(AtomicBool::new(false));
-
-        let (mut stream, _hardware_sample_rate) =
-            Self::try_open_stream(&device, &config, &sender, Arc::clone(&stream_broken))?;
-
-        stream
-            .play()
-            .map_err(|e| AudioCaptureError::Stream(e.to_string()))?;
-
-        let _host = host;
-        let mut last_recreation = Instant::now();
+        // Shared across stream recreations so sample indices keep increasing
+        let sample_counter = Arc::new(AtomicU64::new(0));
+
+        let mut stream: Option<CpalStream> = None;
+        let mut next_attempt = Instant::now();
+        let mut backoff = REOPEN_BACKOFF_MIN;
+        // Set once a stream has run; only then may the default input stand in
+        // for a configured device that disappeared
+        let mut opened_once = false;
+        // Whether the running stream is the default input standing in for the
+        // configured device, and when that device was last looked for
+        let mut on_fallback = false;
+        let mut last_preferred_check = Instant::now();
+        // When capture went down, and when consumers were last told
+        let mut deaf_since: Option<Instant> = None;
+        let mut last_notice = Instant::now();
+        // Watchdog: when the sample counter last moved
+        let mut last_count = 0;
+        let mut last_progress = Instant::now();
 
         loop {
             if stop_receiver.try_recv().is_ok() {
@@ -138,48 +187,172 @@ impl AudioCapture {
                 break;
             }
 
-            if stream_broken.load(Ordering::Acquire)
-                && last_recreation.elapsed() >= Duration::from_millis(500)
-            {
-                log::warn!("Capture stream broken (xrun), recreating...");
-                drop(stream);
-                stream_broken.store(false, Ordering::Release);
-
-                match Self::try_open_stream(
-                    &device,
-                    &config,
-                    &sender,
-                    Arc::clone(&stream_broken),
-                ) {
-                    Ok((new_stream, _)) => {
-                        if let Err(e) = new_stream.play() {
-                            log::error!("Failed to restart capture stream: {}", e);
-                            break;
+            if stream.is_some() {
+                let count = sample_counter.load(Ordering::Relaxed);
+                if count != last_count {
+                    last_count = count;
+                    last_progress = Instant::now();
+                    if let Some(since) = deaf_since.take() {
+                        log::info!(
+                            "🎤 Audio capture restored after {:.1}s",
+                            since.elapsed().as_secs_f64()
+                        );
+                        let _ = status_sender.try_send(CaptureStatus::Restored {
+                            deaf_for: since.elapsed(),
+                        });
+                    }
+                }
+
+                let stalled = last_progress.elapsed() >= STALL_TIMEOUT;
+                if stream_broken.load(Ordering::Acquire) || stalled {
+                    let reason = if stalled {
+                        format!("no audio from the device for {:?}", STALL_TIMEOUT)
+                    } else {
+                        "stream broken (xrun or device error)".to_string()
+                    };
+                    log::warn!("Capture {}, reopening...", reason);
+                    // Either way the audio since the last progress is gone
+                    if deaf_since.is_none() || last_notice.elapsed() >= LOST_NOTICE_INTERVAL {
+                        deaf_since.get_or_insert(last_progress);
+                        last_notice = Instant::now();
+                        let _ = status_sender.try_send(CaptureStatus::Lost(reason));
+                    }
+                    stream = None;
+                    stream_broken.store(false, Ordering::Release);
+                    next_attempt = Instant::now() + REOPEN_BACKOFF_MIN;
+                } else if on_fallback && last_preferred_check.elapsed() >= PREFERRED_DEVICE_RECHECK
+                {
+                    last_preferred_check = Instant::now();
+                    if let Some(id) = config.device_id.as_deref() {
+                        if matches!(Self::find_named_device(&host, id), Ok(Some(_))) {
+                            log::info!(
+                                "🎤 Device {} is back, switching from the default input",
+                                id
+                            );
+                            stream = None;
+                            next_attempt = Instant::now();
                         }
-                        stream = new_stream;
-                        last_recreation = Instant::now();
-                        log::info!("Capture stream recreated successfully after xrun");
+                    }
+                }
+            }
+
+            if stream.is_none() && Instant::now() >= next_attempt {
+                if deaf_since.is_some() || opened_once {
+                    // Skip the indices of the audio missed since the last
+                    // stream, so they stay aligned with the audio timeline
+                    let missed = last_progress.elapsed().as_millis() as u64
+                        * OUTPUT_SAMPLE_RATE as u64
+                        / 1000;
+                    sample_counter.fetch_add(missed, Ordering::Relaxed);
+                    last_progress = Instant::now();
+                }
+
+                let opened = Self::find_device(&host, &config, opened_once).and_then(
+                    |(device, fallback)| {
+                        log::info!("🎤 Using input device: {:?}", device.name());
+                        let (new_stream, _) = Self::try_open_stream(
+                            &device,
+                            &config,
+                            &sender,
+                            Arc::clone(&stream_broken),
+                            Arc::clone(&sample_counter),
+                        )?;
+                        new_stream
+                            .play()
+                            .map_err(|e| AudioCaptureError::Stream(e.to_string()))?;
+                        Ok((new_stream, fallback))
+                    },
+                );
+
+                match opened {
+                    Ok((new_stream, fallback)) => {
+                        // While deaf, capture counts as restored once chunks flow
+                        if deaf_since.is_none() && opened_once {
+                            log::info!("Capture stream recreated successfully");
+                        }
+                        stream = Some(new_stream);
+                        opened_once = true;
+                        on_fallback = fallback;
+                        last_preferred_check = Instant::now();
+                        backoff = REOPEN_BACKOFF_MIN;
+                        last_count = sample_counter.load(Ordering::Relaxed);
+                        last_progress = Instant::now();
                     }
                     Err(e) => {
-                        log::error!("Failed to recreate capture stream: {}", e);
-                        break;
+                        log::error!(
+                            "Failed to open capture stream: {} (retrying in {:.1}s)",
+                            e,
+                            backoff.as_secs_f64()
+                        );
+                        if deaf_since.is_none() || last_notice.elapsed() >= LOST_NOTICE_INTERVAL {
+                            deaf_since.get_or_insert_with(Instant::now);
+                            last_notice = Instant::now();
+                            let _ = status_sender.try_send(CaptureStatus::Lost(e.to_string()));
+                        }
+                        next_attempt = Instant::now() + backoff;
+                        backoff = (backoff * 2).min(REOPEN_BACKOFF_MAX);
                     }
                 }
             }
 
             thread::sleep(Duration::from_millis(100));
         }
+    }
 
-        Ok(())
+    /// The configured input device, and whether the default input stands in
+    /// for it. The configured device is always preferred; only once
+    /// `allow_default` is set does the default input replace a named device
+    /// that has disappeared.
+    fn find_device(
+        host: &cpal::Host,
+        config: &AudioCaptureConfig,
+        allow_default: bool,
+    ) -> Result<(Device, bool), AudioCaptureError> {
+        let default_device = || {
+            host.default_input_device()
+                .ok_or_else(|| AudioCaptureError::Device("No default input device found".into()))
+        };
+
+        let id = match config.device_id.as_deref() {
+            None | Some("default") => return Ok((default_device()?, false)),
+            Some(id) => id,
+        };
+        match Self::find_named_device(host, id)? {
+            Some(device) => Ok((device, false)),
+            None if allow_default => {
+                log::warn!(
+                    "🎤 Device {} not found, falling back to the default input",
+                    id
+                );
+                Ok((default_device()?, true))
+            }
+            None => Err(AudioCaptureError::Device(format!(
+                "Device not found: {}",
+                id
+            ))),
+        }
+    }
+
+    fn find_named_device(host: &cpal::Host, id: &str) -> Result<Option<Device>, AudioCaptureError> {
+        Ok(host
+            .devices()
+            .map_err(|e| AudioCaptureError::Device(e.to_string()))?
+            .find(|d| d.name().map(|n| n == id).unwrap_or(false)))
     }
 
     fn try_open_stream(
         device: &Device,
         config: &AudioCaptureConfig,
-        sender: &Sender<Vec<u8>>,
+        sender: &Sender<CapturedChunk>,
         stream_broken: Arc<AtomicBool>,
+        sample_counter: Arc<AtomicU64>,
     ) -> Result<(CpalStream, u32), AudioCaptureError> {
-        let supported_config = match Self::select_input_config(device, config.channel) {
+        let highest_channel = config
+            .required_channels
+            .iter()
+            .copied()
+            .fold(config.channel, u32::max);
+        let supported_config = match Self::select_input_config(device, highest_channel) {
             Ok(cfg) => cfg,
             Err(err) => {
                 log::warn!(
@@ -199,45 +372,73 @@ impl AudioCapture {
                 supported_config.channels()
             )));
         }
+        if let Some(channel) = config
+            .required_channels
+            .iter()
+            .find(|&&channel| channel >= u32::from(supported_config.channels()))
+        {
+            return Err(AudioCaptureError::Config(format!(
+                "Channel {} is not available (device has {} channels)",
+                channel,
+                supported_config.channels()
+            )));
+        }
 
         let stream_config = supported_config.config();
         let hardware_sample_rate = stream_config.sample_rate.0;
         let channels = stream_config.channels as usize;
+        let sample_format = supported_config.sample_format();
 
-        if supported_config.sample_format() != SampleFormat::I16 {
-            return Err(AudioCaptureError::Config(format!(
-                "Expected I16 sample format, got {:?}",
-                supported_config.sample_format()
-            )));
-        }
-        if hardware_sample_rate != 16000 {
-            return Err(AudioCaptureError::Config(format!(
-                "Expected 16kHz sample rate, got {}Hz",
-                hardware_sample_rate
-            )));
-        }
-
+        let converter = CaptureConverter::new(config, channels, hardware_sample_rate);
         log::info!(
-            "🎤 Hardware: {}Hz, {} channels, I16 → Output: 16kHz mono s16le",
+            "🎤 Hardware: {}Hz, {} channels, {:?} → Output: 16kHz mono s16le{}",
             hardware_sample_rate,
             channels,
+            sample_format,
+            if hardware_sample_rate == OUTPUT_SAMPLE_RATE {
+                String::new()
+            } else {
+                format!(" ({} resampling)", config.resample_quality)
+            }
         );
 
-        let sender = sender.clone();
-        let stream = Self::create_native_i16_stream(
-            device,
-            &stream_config,
-            config.channel,
-            channels,
-            config.gain,
-            sender,
-            stream_broken,
-        )?;
+        macro_rules! build {
+            ($sample:ty) => {
+                Self::create_input_stream::<$sample>(
+                    device,
+                    &stream_config,
+                    converter,
+                    sender.clone(),
+                    stream_broken,
+                    sample_counter,
+                )
+            };
+        }
+        let stream = match sample_format {
+            SampleFormat::I8 => build!(i8),
+            SampleFormat::I16 => build!(i16),
+            SampleFormat::I24 => build!(cpal::I24),
+            SampleFormat::I32 => build!(i32),
+            SampleFormat::I64 => build!(i64),
+            SampleFormat::U8 => build!(u8),
+            SampleFormat::U16 => build!(u16),
+            SampleFormat::U32 => build!(u32),
+            SampleFormat::U64 => build!(u64),
+            SampleFormat::F32 => build!(f32),
+            SampleFormat::F64 => build!(f64),
+            other => {
+                return Err(AudioCaptureError::Config(format!(
+                    "Unsupported sample format {:?}",
+                    other
+                )))
+            }
+        }?;
 
         Ok((stream, hardware_sample_rate))
     }
 
-    /// Prefer I16 at 16kHz; reject anything else.
+    /// Prefer configs that need no conversion: 16kHz first (resampling costs
+    /// CPU and some quality), then I16, then other formats by fidelity.
     fn select_input_config(
         device: &Device,
         channel: u32,
@@ -258,19 +459,21 @@ impl AudioCapture {
 
             let format_rank = match config_range.sample_format() {
                 SampleFormat::I16 => 0,
+                SampleFormat::I24 | SampleFormat::I32 | SampleFormat::F32 => 1,
+                SampleFormat::F64 => 2,
                 _ => 3,
             };
 
             let min_rate = config_range.min_sample_rate().0;
             let max_rate = config_range.max_sample_rate().0;
-            let target_rate = 16000;
+            let target_rate = OUTPUT_SAMPLE_RATE;
 
             let chosen_rate = target_rate.clamp(min_rate, max_rate);
             let rate_diff = chosen_rate.abs_diff(target_rate);
             let config = config_range.with_sample_rate(cpal::SampleRate(chosen_rate));
 
-            if format_rank < best_format_rank
-                || (format_rank == best_format_rank && rate_diff < best_rate_diff)
+            if rate_diff < best_rate_diff
+                || (rate_diff == best_rate_diff && format_rank < best_format_rank)
             {
                 best_format_rank = format_rank;
                 best_rate_diff = rate_diff;
@@ -283,27 +486,28 @@ impl AudioCapture {
         })
     }
 
-    /// Allocation-free I16 capture path.
-    /// Pre-allocates all buffers; the only allocation per chunk is the Vec<u8>
-    /// sent over the channel (~12.5Hz), not per-sample or per-callback.
-    fn create_native_i16_stream(
+    /// Allocation-free capture path for any sample format.
+    /// `converter` pre-allocates its buffers; the only allocations per chunk
+    /// are the Vecs sent over the channel (~12.5Hz), not per-sample or
+    /// per-callback.
+    fn create_input_stream<T>(
         device: &Device,
         config: &cpal::StreamConfig,
-        channel: u32,
-        channels: usize,
-        gain: f32,
-        sender: Sender<Vec<u8>>,
+        mut converter: CaptureConverter,
+        sender: Sender<CapturedChunk>,
         stream_broken: Arc<AtomicBool>,
-    ) -> Result<CpalStream, AudioCaptureError> {
-        let chunk_bytes = CHUNK_SIZE * 2;
-        let mut byte_buffer: Vec<u8> = Vec::with_capacity(chunk_bytes * 8);
-        let mut chunk_buf: Vec<u8> = vec![0u8; chunk_bytes];
+        sample_counter: Arc<AtomicU64>,
+    ) -> Result<CpalStream, AudioCaptureError>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
         let affinity_set = Arc::new(AtomicBool::new(false));
 
         device
             .build_input_stream(
                 config,
-                move |data: &[i16], _| {
+                move |data: &[T], _| {
                     #[cfg(target_os = "linux")]
                     if !affinity_set.load(Ordering::Relaxed) {
                         affinity_set.store(true, Ordering::Relaxed);
@@ -326,33 +530,23 @@ impl AudioCapture {
                         }
                     }
 
-                    for frame in data.chunks(channels) {
-                        if let Some(&s) = frame.get(channel as usize) {
-                            let sample = if gain != 1.0 {
-                                (s as f32 * gain)
-                                    .round()
-                                    .clamp(i16::MIN as f32, i16::MAX as f32)
-                                    as i16
-                            } else {
-                                s
-                            };
-                            byte_buffer.extend_from_slice(&sample.to_le_bytes());
-                        }
-                    }
+                    converter.push(data);
 
                     let mut read_pos = 0;
-                    while read_pos + chunk_bytes <= byte_buffer.len() {
-                        chunk_buf
-                            .copy_from_slice(&byte_buffer[read_pos..read_pos + chunk_bytes]);
-                        if sender.try_send(chunk_buf.clone()).is_err() {
-                            read_pos += chunk_bytes;
+                    while read_pos + CHUNK_SIZE <= converter.pending[converter.stream].len() {
+                        let sample_index =
+                            sample_counter.fetch_add(CHUNK_SIZE as u64, Ordering::Relaxed);
+                        let chunk = converter.chunk(read_pos, sample_index);
+                        read_pos += CHUNK_SIZE;
+                        if sender.try_send(chunk).is_err() {
                             break;
                         }
-                        read_pos += chunk_bytes;
                     }
 
                     if read_pos > 0 {
-                        byte_buffer.drain(..read_pos);
+                        for pending in &mut converter.pending {
+                            pending.drain(..read_pos);
+                        }
                     }
                 },
                 move |err| {
@@ -399,6 +593,106 @@ impl AudioCapture {
     }
 }
 
+/// Turns interleaved device frames of any format and rate into 16kHz s16
+/// samples for each delivered channel. Buffers are reused across callbacks.
+struct CaptureConverter {
+    device_channels: usize,
+    /// Device channels delivered, in output order
+    delivered: Vec<usize>,
+    /// Index in `delivered` of the streamed channel
+    stream: usize,
+    all_channels: bool,
+    gain: f32,
+    resamplers: Vec<Resampler>,
+    /// Per delivered channel: this callback's samples at the device rate...
+    input: Vec<Vec<f32>>,
+    /// ...the same samples at 16kHz...
+    resampled: Vec<Vec<f32>>,
+    /// ...and converted samples waiting for a full chunk
+    pending: Vec<Vec<i16>>,
+}
+
+impl CaptureConverter {
+    fn new(config: &AudioCaptureConfig, device_channels: usize, device_rate: u32) -> Self {
+        let delivered: Vec<usize> = if config.all_channels {
+            (0..device_channels).collect()
+        } else {
+            vec![config.channel as usize]
+        };
+        let stream = delivered
+            .iter()
+            .position(|&c| c == config.channel as usize)
+            .unwrap_or(0);
+
+        Self {
+            device_channels,
+            stream,
+            all_channels: config.all_channels,
+            gain: config.gain,
+            resamplers: delivered
+                .iter()
+                .map(|_| Resampler::new(device_rate, OUTPUT_SAMPLE_RATE, config.resample_quality))
+                .collect(),
+            input: vec![Vec::with_capacity(CHUNK_SIZE * 8); delivered.len()],
+            resampled: vec![Vec::with_capacity(CHUNK_SIZE * 8); delivered.len()],
+            pending: vec![Vec::with_capacity(CHUNK_SIZE * 8); delivered.len()],
+            delivered,
+        }
+    }
+
+    /// Convert one callback's interleaved frames and append them to `pending`
+    fn push<T>(&mut self, data: &[T])
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        for input in &mut self.input {
+            input.clear();
+        }
+        for frame in data.chunks_exact(self.device_channels) {
+            for (input, &channel) in self.input.iter_mut().zip(&self.delivered) {
+                input.push(frame[channel].to_sample::<f32>());
+            }
+        }
+
+        let scale = 32768.0 * self.gain;
+        for ((resampler, input), (resampled, pending)) in self
+            .resamplers
+            .iter_mut()
+            .zip(&self.input)
+            .zip(self.resampled.iter_mut().zip(&mut self.pending))
+        {
+            resampled.clear();
+            resampler.process(input, resampled);
+            pending.extend(
+                resampled
+                    .iter()
+                    .map(|&x| (x * scale).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16),
+            );
+        }
+    }
+
+    /// The `CHUNK_SIZE` pending samples starting at `offset`
+    fn chunk(&self, offset: usize, sample_index: u64) -> CapturedChunk {
+        let span = offset..offset + CHUNK_SIZE;
+        CapturedChunk {
+            data: self.pending[self.stream][span.clone()]
+                .iter()
+                .flat_map(|s| s.to_le_bytes())
+                .collect(),
+            sample_index,
+            channels: if self.all_channels {
+                self.pending
+                    .iter()
+                    .map(|p| p[span.clone()].to_vec())
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
 impl Drop for AudioCapture {
     fn drop(&mut self) {
         log::debug!("🎤 Dropping AudioCapture - sending stop signal");
@@ -406,3 +700,63 @@ impl Drop for AudioCapture {
         thread::sleep(Duration::from_millis(10));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_format_passes_through() {
+        let config = AudioCaptureConfig {
+            channel: 1,
+            gain: 2.0,
+            ..Default::default()
+        };
+        let mut converter = CaptureConverter::new(&config, 2, OUTPUT_SAMPLE_RATE);
+        let frames: Vec<i16> = (0..CHUNK_SIZE as i16).flat_map(|n| [-1, n * 20]).collect();
+        converter.push(&frames);
+
+        let chunk = converter.chunk(0, 0);
+        let samples: Vec<i16> = chunk
+            .data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        let expected: Vec<i16> = (0..CHUNK_SIZE as i16)
+            .map(|n| (n as i32 * 40).min(i16::MAX as i32) as i16)
+            .collect();
+        assert_eq!(samples, expected);
+        assert!(chunk.channels.is_empty());
+    }
+
+    #[test]
+    fn test_converts_float_48khz_stereo() {
+        let config = AudioCaptureConfig {
+            channel: 0,
+            all_channels: true,
+            ..Default::default()
+        };
+        let mut converter = CaptureConverter::new(&config, 2, 48000);
+        // 100ms of a 500Hz tone on the left, silence on the right
+        let frames: Vec<f32> = (0..4800)
+            .flat_map(|n| {
+                let t = n as f32 / 48000.0;
+                [0.5 * (2.0 * std::f32::consts::PI * 500.0 * t).sin(), 0.0]
+            })
+            .collect();
+        converter.push(&frames);
+
+        assert!(converter.pending[0].len() >= CHUNK_SIZE);
+        let chunk = converter.chunk(0, 0);
+        assert_eq!(chunk.channels.len(), 2);
+        assert_eq!(chunk.channels[0].len(), CHUNK_SIZE);
+        assert!(chunk.channels[1].iter().all(|&s| s == 0));
+
+        // Half scale at 16kHz; skip the resampler's start-up edge
+        for (n, &sample) in chunk.channels[0].iter().enumerate().skip(50) {
+            let t = n as f32 / 16000.0;
+            let ideal = 16384.0 * (2.0 * std::f32::consts::PI * 500.0 * t).sin();
+            assert!((sample as f32 - ideal).abs() < 200.0, "{}: {}", n, sample);
+        }
+    }
+}
@@ -4,19 +4,23 @@ use std::net::TcpStream;
 use std::thread;
 use std::time::Duration;
 
-use audio::protocol::{ProducerConnection, ProducerMessage};
+use audio::protocol::{AudioEncoding, ProducerConnection, ProducerMessage, CAP_STREAM_FORMAT};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
     let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 || args.len() > 3 {
-        eprintln!("Usage: {} <audio_file.raw> [server_address:port]", args[0]);
+    if args.len() < 2 || args.len() > 4 {
+        eprintln!(
+            "Usage: {} <audio_file.raw> [server_address:port] [rate:channels:encoding]",
+            args[0]
+        );
         eprintln!();
-        eprintln!("Audio file should be mono 48kHz s16le format.");
+        eprintln!("Audio file defaults to mono 48kHz s16le format.");
         eprintln!("Server address defaults to 127.0.0.1:8081");
-        eprintln!("To convert from other formats:");
-        eprintln!("  ffmpeg -i input.wav -f s16le -ar 48000 -ac 1 output.raw");
+        eprintln!("Other formats are declared and converted by the server, e.g.");
+        eprintln!("  {} tts.raw 127.0.0.1:8081 22050:1:s16le", args[0]);
+        eprintln!("Encodings: s16le, f32le");
         std::process::exit(1);
     }
 
@@ -26,6 +30,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else {
         "127.0.0.1:8081"
     };
+    let format = args.get(3).map(|f| parse_format(f)).transpose()?;
+    let (sample_rate, channels, encoding) = format.unwrap_or((48000, 1, AudioEncoding::S16le));
+    let frame_bytes = channels as usize
+        * match encoding {
+            AudioEncoding::F32le => 4,
+            _ => 2,
+        };
 
     // Read the raw audio file
     println!("📁 Reading audio file: {}", audio_file);
@@ -33,23 +44,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut audio_data = Vec::new();
     file.read_to_end(&mut audio_data)?;
 
-    if audio_data.len() % 2 != 0 {
-        return Err("Audio file size is not aligned to 16-bit samples".into());
+    if audio_data.len() % frame_bytes != 0 {
+        return Err("Audio file size is not aligned to whole frames".into());
     }
 
-    let sample_count = audio_data.len() / 2;
-    let duration_seconds = sample_count as f64 / 48000.0;
+    let frame_count = audio_data.len() / frame_bytes;
+    let duration_seconds = frame_count as f64 / sample_rate as f64;
     println!(
-        "🎵 Audio file: {} samples ({:.2}s at 48kHz)",
-        sample_count, duration_seconds
+        "🎵 Audio file: {} frames ({:.2}s at {}Hz, {} channel(s), {})",
+        frame_count, duration_seconds, sample_rate, channels, encoding
     );
 
     // Connect to audio server
     println!("🔌 Connecting to audio server at {}...", server_addr);
     let stream = TcpStream::connect(server_addr)?;
     let mut connection = ProducerConnection::new(stream);
+    let session = connection.handshake(CAP_STREAM_FORMAT)?;
 
-    println!("✅ Connected to audio server");
+    println!(
+        "✅ Connected to audio server (protocol v{})",
+        session.version
+    );
 
     // Send audio in chunks (simulate streaming)
     const CHUNK_SIZE: usize = 1024 * 2; // 1024 samples = 2048 bytes
@@ -59,6 +74,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let stream_id = ProducerMessage::current_timestamp();
     println!("🆔 Stream ID: {}", stream_id);
 
+    if format.is_some() {
+        if !session.supports(CAP_STREAM_FORMAT) {
+            return Err("Server does not convert declared stream formats".into());
+        }
+        connection.write_message(&ProducerMessage::StreamFormat {
+            stream_id,
+            sample_rate,
+            channels,
+            encoding,
+        })?;
+    }
+
     for chunk in audio_data.chunks(CHUNK_SIZE) {
         let play_msg = ProducerMessage::Play {
             data: chunk.to_vec(),
@@ -114,3 +141,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Parse `rate:channels:encoding`, e.g. `22050:1:s16le`
+fn parse_format(format: &str) -> Result<(u32, u16, AudioEncoding), Box<dyn std::error::Error>> {
+    let parts: Vec<&str> = format.split(':').collect();
+    let [rate, channels, encoding] = parts[..] else {
+        return Err(format!("Expected rate:channels:encoding, got '{}'", format).into());
+    };
+    Ok((rate.parse()?, channels.parse()?, encoding.parse()?))
+}
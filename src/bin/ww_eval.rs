@@ -0,0 +1,587 @@
+//! Offline wake word evaluation.
+//!
+//! Feeds labelled recordings through the same `wakeword_model::Model` and
+//! threshold/debounce logic as `ConsumerServer::detection_thread`, one
+//! 1280-sample hop at a time, and prints one JSON line per file to stdout.
+//! An aggregate summary (recall, false accepts per hour) goes to stderr.
+//!
+//! Inputs are 16kHz mono s16le, either `.wav` or headerless `.raw`. A file is
+//! labelled by an optional sidecar `<stem>.json` next to it:
+//!
+//! ```json
+//! {"wake": true, "wake_onset_s": 1.24}
+//! ```
+//!
+//! Files without a sidecar use `--label` (default: no wake word).
+//!
+//! With `--inference-gate`, every file is also replayed through a second,
+//! energy-gated copy of the model. Its detections and skipped inference are
+//! reported next to the ungated ones, and the run fails if gating loses a
+//! detection of a wake word recording.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use audio::audio_source::CHUNK_SIZE;
+use audio::wakeword_gate::{
+    DetectionOverride, DetectionSettings, WakewordFire, WakewordGate, WAKEWORD_DEBOUNCE_MS,
+};
+use audio::wakeword_inference_gate::{InferenceGate, InferenceGateConfig, InferenceStats};
+use audio::wakeword_model::Model;
+use audio::wakeword_models::ModelRegistry;
+use clap::{Parser, ValueEnum};
+use serde_json::{json, Value};
+
+const SAMPLE_RATE: u32 = 16000;
+const HOP_SECONDS: f64 = CHUNK_SIZE as f64 / SAMPLE_RATE as f64;
+
+#[derive(Parser)]
+#[command(name = "ww_eval")]
+#[command(about = "Score WAV/raw recordings through the production wakeword pipeline")]
+#[command(long_about = "
+Score 16kHz mono s16le recordings (.wav or .raw) through the same wakeword
+model, 1280-sample hops and threshold/debounce logic used by the live
+detection thread.
+
+Per-file results are written as JSON lines; a summary with recall and
+false accepts per hour is printed to stderr.
+
+EXAMPLES:
+  # Score a directory of labelled recordings
+  ww_eval recordings/
+
+  # Compare a stricter threshold, resetting model state between files
+  ww_eval --threshold 0.6 --reset-per-file recordings/ > results.jsonl
+
+  # Treat unlabelled files as wake word recordings and keep the hop trace
+  ww_eval --label wake --trace positives/*.wav
+
+  # Check that energy-gated inference keeps recall on a corpus
+  ww_eval --inference-gate --inference-gate-dbfs -60 recordings/
+")]
+struct Args {
+    /// Recordings or directories to score (directories are searched recursively)
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Wakeword model name or .tflite path (repeatable)
+    #[arg(long = "model", default_value = "hey_mycroft")]
+    models: Vec<String>,
+
+    /// Directory containing the wakeword and feature models
+    #[arg(long, default_value = "models")]
+    model_dir: String,
+
+    /// Detection threshold for every model without an override (default: manifest threshold, or 0.5)
+    #[arg(long)]
+    threshold: Option<f32>,
+
+    /// Minimum time in ms between two detections of the same model, in audio time
+    #[arg(long, default_value_t = WAKEWORD_DEBOUNCE_MS)]
+    debounce_ms: u64,
+
+    /// Time in ms after a detection during which no model may fire, in audio time
+    #[arg(long, default_value_t = WAKEWORD_DEBOUNCE_MS)]
+    refractory_ms: u64,
+
+    /// Per-model detection override, e.g. `hey_mycroft:threshold=0.6` (repeatable)
+    #[arg(long = "wakeword-detection", value_name = "MODEL:KEY=VALUE,...")]
+    wakeword_detection: Vec<String>,
+
+    /// Lowest confidence reported as a near-miss
+    #[arg(long, default_value = "0.1")]
+    near_miss_floor: f32,
+
+    /// Label for files without a sidecar
+    #[arg(long, value_enum, default_value = "nowake")]
+    label: Label,
+
+    /// Reset model state before each file (default: stream files back to back, like live)
+    #[arg(long)]
+    reset_per_file: bool,
+
+    /// Include the per-hop confidence trace in the output
+    #[arg(long)]
+    trace: bool,
+
+    /// Write per-file results here instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Also replay every file with energy-gated inference and fail if it
+    /// misses a wake word the ungated model detects
+    #[arg(long)]
+    inference_gate: bool,
+
+    /// Hops at or above this RMS level (dBFS) reopen the inference gate
+    #[arg(long, default_value = "-55", allow_negative_numbers = true)]
+    inference_gate_dbfs: f32,
+
+    /// Milliseconds of continuous quiet before the inference gate closes
+    #[arg(long, default_value = "2000")]
+    inference_gate_hold_ms: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Label {
+    Wake,
+    Nowake,
+}
+
+/// Ground truth for one recording.
+struct Sidecar {
+    wake: bool,
+    wake_onset_s: Option<f64>,
+}
+
+/// Running totals across all scored files.
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    positives: usize,
+    detected: usize,
+    negative_seconds: f64,
+    false_accepts: usize,
+    latencies: Vec<f64>,
+    gated_detected: usize,
+    gated_false_accepts: usize,
+    /// Wake word recordings detected ungated but not gated
+    gated_misses: usize,
+}
+
+/// Second model and detection gate fed the same hops with inference gated.
+struct GatedReplay {
+    model: Model,
+    gate: WakewordGate,
+    inference: InferenceGate,
+}
+
+impl GatedReplay {
+    /// Run one hop through the gated pipeline.
+    fn hop(
+        &mut self,
+        chunk: &[i16],
+        now: Duration,
+    ) -> Result<Option<WakewordFire>, Box<dyn std::error::Error>> {
+        if !self.inference.should_run(chunk, false) {
+            self.model.skip(chunk)?;
+            return Ok(None);
+        }
+        let predictions = self.model.predict(chunk, None, 1.0)?;
+        Ok(self.gate.select(&predictions, now).0)
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let args = Args::parse();
+
+    let mut files = Vec::new();
+    for input in &args.inputs {
+        collect_recordings(input, &mut files)?;
+    }
+    files.sort();
+    if files.is_empty() {
+        return Err("no .wav or .raw recordings found".into());
+    }
+
+    let detection = DetectionSettings {
+        threshold: args
+            .threshold
+            .unwrap_or(DetectionSettings::default().threshold),
+        debounce_ms: args.debounce_ms,
+        refractory_ms: args.refractory_ms,
+    };
+    let overrides = args
+        .wakeword_detection
+        .iter()
+        .map(|spec| DetectionOverride::parse(spec))
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| format!("--wakeword-detection: {}", e))?;
+    // Manifest thresholds apply unless a global threshold was given explicitly
+    let model_detection = ModelRegistry::discover(&args.model_dir)?.detection_settings(
+        &args.models,
+        &detection,
+        args.threshold.is_none(),
+        &overrides,
+    );
+    let mut gate = WakewordGate::new(detection, model_detection);
+
+    let mut model = Model::new_with_model_path(args.models.clone(), vec![], &args.model_dir)?;
+    let mut gated = if args.inference_gate {
+        Some(GatedReplay {
+            model: Model::new_with_model_path(args.models.clone(), vec![], &args.model_dir)?,
+            gate: gate.clone(),
+            inference: InferenceGate::new(&InferenceGateConfig {
+                open_dbfs: args.inference_gate_dbfs,
+                hold_ms: args.inference_gate_hold_ms,
+            }),
+        })
+    } else {
+        None
+    };
+    let settings: serde_json::Map<String, Value> = model
+        .get_model_inputs()
+        .keys()
+        .map(|name| {
+            let s = gate.settings(name);
+            let value = json!({
+                "threshold": s.threshold,
+                "debounce_ms": s.debounce_ms,
+                "refractory_ms": s.refractory_ms,
+            });
+            (name.clone(), value)
+        })
+        .collect();
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let mut summary = Summary::default();
+    for path in &files {
+        if args.reset_per_file {
+            model.reset()?;
+        }
+        // Detections never carry over between files; each file starts on its own clock.
+        gate.reset();
+        if let Some(replay) = gated.as_mut() {
+            if args.reset_per_file {
+                replay.model.reset()?;
+                replay.inference.reset();
+            }
+            replay.gate.reset();
+        }
+
+        let samples = match read_recording(path) {
+            Ok(samples) => samples,
+            Err(e) => {
+                log::warn!("⚠️ Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let sidecar = read_sidecar(path)?.unwrap_or(Sidecar {
+            wake: args.label == Label::Wake,
+            wake_onset_s: None,
+        });
+
+        let record = score_file(
+            &mut model,
+            &mut gate,
+            gated.as_mut(),
+            &args,
+            path,
+            &samples,
+            &sidecar,
+        )?;
+        summary.add(&record, &sidecar);
+        writeln!(out, "{}", record)?;
+    }
+    out.flush()?;
+
+    let inference = gated.as_ref().map(|replay| replay.inference.stats());
+    eprintln!("{}", summary.to_json(Value::Object(settings), inference));
+    if summary.gated_misses > 0 {
+        return Err(format!(
+            "inference gate missed {} wake word recording(s) detected without it",
+            summary.gated_misses
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Run one recording through the model and build its JSON record.
+fn score_file(
+    model: &mut Model,
+    gate: &mut WakewordGate,
+    mut gated: Option<&mut GatedReplay>,
+    args: &Args,
+    path: &Path,
+    samples: &[i16],
+    sidecar: &Sidecar,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut fires = Vec::new();
+    let mut near_misses = Vec::new();
+    let mut trace = Vec::new();
+    let mut peak_confidence = 0.0f32;
+    let mut peak_time_s = 0.0f64;
+    // Open near-miss run: (start time, peak confidence, peak time)
+    let mut near_miss: Option<(f64, f32, f64)> = None;
+    let mut gated_fires = Vec::new();
+    let inference_before = gated.as_ref().map(|replay| replay.inference.stats());
+
+    let hops = samples.len() / CHUNK_SIZE;
+    for (hop, chunk) in samples.chunks_exact(CHUNK_SIZE).enumerate() {
+        let predictions = model.predict(chunk, None, 1.0)?;
+        // A hop's result is available once its last sample has arrived.
+        let time_s = (hop + 1) as f64 * HOP_SECONDS;
+        let now =
+            Duration::from_millis((hop as u64 + 1) * CHUNK_SIZE as u64 * 1000 / SAMPLE_RATE as u64);
+        let (fired, max_conf) = gate.select(&predictions, now);
+        if let Some(replay) = gated.as_deref_mut() {
+            if let Some(fire) = replay.hop(chunk, now)? {
+                gated_fires.push(json!({
+                    "time_s": round(time_s),
+                    "confidence": round(fire.confidence as f64),
+                    "model": fire.model,
+                }));
+            }
+        }
+
+        if args.trace {
+            trace.push(json!(round(max_conf as f64)));
+        }
+        if max_conf > peak_confidence {
+            peak_confidence = max_conf;
+            peak_time_s = time_s;
+        }
+
+        if let Some(fire) = fired {
+            fires.push(json!({
+                "time_s": round(time_s),
+                "confidence": round(fire.confidence as f64),
+                "threshold": fire.threshold,
+                "model": fire.model,
+            }));
+        }
+
+        // Hops where some model crossed its threshold are detections (or
+        // debounced repeats of one), never near-misses.
+        let crossed = predictions
+            .iter()
+            .any(|(name, &confidence)| confidence >= gate.settings(name).threshold);
+        if !crossed && max_conf >= args.near_miss_floor {
+            let run = near_miss.get_or_insert((time_s - HOP_SECONDS, max_conf, time_s));
+            if max_conf > run.1 {
+                run.1 = max_conf;
+                run.2 = time_s;
+            }
+        } else if let Some((start_s, peak, peak_s)) = near_miss.take() {
+            // A run that ends in a detection was the wake word rising, not a miss.
+            if !crossed {
+                near_misses.push(json!({
+                    "start_s": round(start_s),
+                    "end_s": round(time_s - HOP_SECONDS),
+                    "peak_confidence": round(peak as f64),
+                    "peak_time_s": round(peak_s),
+                }));
+            }
+        }
+    }
+    if let Some((start_s, peak, peak_s)) = near_miss {
+        near_misses.push(json!({
+            "start_s": round(start_s),
+            "end_s": round(hops as f64 * HOP_SECONDS),
+            "peak_confidence": round(peak as f64),
+            "peak_time_s": round(peak_s),
+        }));
+    }
+
+    let first_fire_s = fires.first().and_then(|f| f["time_s"].as_f64());
+    let detection_latency_s = match (first_fire_s, sidecar.wake_onset_s) {
+        (Some(fire), Some(onset)) if sidecar.wake => Some(round(fire - onset)),
+        _ => None,
+    };
+
+    let mut record = json!({
+        "file": path.display().to_string(),
+        "wake": sidecar.wake,
+        "duration_s": round(samples.len() as f64 / SAMPLE_RATE as f64),
+        "hops": hops,
+        "peak_confidence": round(peak_confidence as f64),
+        "peak_time_s": round(peak_time_s),
+        "fired": !fires.is_empty(),
+        "fires": fires,
+        "near_misses": near_misses,
+        "wake_onset_s": sidecar.wake_onset_s,
+        "detection_latency_s": detection_latency_s,
+    });
+    if args.trace {
+        record["trace"] = Value::Array(trace);
+    }
+    if let (Some(replay), Some(before)) = (gated, inference_before) {
+        let after = replay.inference.stats();
+        record["gated"] = json!({
+            "fired": !gated_fires.is_empty(),
+            "fires": gated_fires,
+            "inference_run": after.run - before.run,
+            "inference_skipped": after.skipped - before.skipped,
+        });
+    }
+    Ok(record)
+}
+
+impl Summary {
+    fn add(&mut self, record: &Value, sidecar: &Sidecar) {
+        self.files += 1;
+        let fires = record["fires"].as_array().map_or(0, |f| f.len());
+        let gated_fires = record["gated"]["fires"].as_array().map_or(0, |f| f.len());
+        if sidecar.wake {
+            self.positives += 1;
+            if fires > 0 {
+                self.detected += 1;
+            }
+            if gated_fires > 0 {
+                self.gated_detected += 1;
+            } else if fires > 0 && !record["gated"].is_null() {
+                self.gated_misses += 1;
+                log::warn!(
+                    "⚠️ Inference gate missed the wake word in {}",
+                    record["file"].as_str().unwrap_or_default()
+                );
+            }
+            if let Some(latency) = record["detection_latency_s"].as_f64() {
+                self.latencies.push(latency);
+            }
+        } else {
+            self.negative_seconds += record["duration_s"].as_f64().unwrap_or(0.0);
+            self.false_accepts += fires;
+            self.gated_false_accepts += gated_fires;
+        }
+    }
+
+    fn to_json(&self, settings: Value, inference: Option<InferenceStats>) -> Value {
+        let recall = (self.positives > 0).then(|| self.detected as f64 / self.positives as f64);
+        let negative_hours = self.negative_seconds / 3600.0;
+        let fa_per_hour =
+            (negative_hours > 0.0).then(|| self.false_accepts as f64 / negative_hours);
+
+        let mut latencies = self.latencies.clone();
+        latencies.sort_by(f64::total_cmp);
+        let median_latency_s = latencies.get(latencies.len() / 2).copied();
+
+        let mut summary = json!({
+            "summary": true,
+            "models": settings,
+            "files": self.files,
+            "positives": self.positives,
+            "detected": self.detected,
+            "recall": recall.map(round),
+            "negative_hours": round(negative_hours),
+            "false_accepts": self.false_accepts,
+            "false_accepts_per_hour": fa_per_hour.map(round),
+            "median_latency_s": median_latency_s.map(round),
+        });
+        if let Some(stats) = inference {
+            let gated_recall =
+                (self.positives > 0).then(|| self.gated_detected as f64 / self.positives as f64);
+            summary["gated"] = json!({
+                "detected": self.gated_detected,
+                "recall": gated_recall.map(round),
+                "missed": self.gated_misses,
+                "false_accepts": self.gated_false_accepts,
+                "inference_run": stats.run,
+                "inference_skipped": stats.skipped,
+                "skipped_ratio": round(stats.skipped_ratio()),
+            });
+        }
+        summary
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10000.0).round() / 10000.0
+}
+
+fn collect_recordings(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            collect_recordings(&entry?.path(), files)?;
+        }
+    } else if matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("wav") | Some("raw")
+    ) {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Load a recording as 16kHz mono i16 samples.
+fn read_recording(path: &Path) -> Result<Vec<i16>, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let pcm = if path.extension().and_then(|e| e.to_str()) == Some("wav") {
+        wav_pcm_data(&bytes)?
+    } else {
+        &bytes[..]
+    };
+
+    if pcm.len() % 2 != 0 {
+        return Err("audio data is not aligned to 16-bit samples".to_string());
+    }
+    Ok(pcm
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect())
+}
+
+/// Return the `data` chunk of a RIFF/WAVE file after checking it is 16kHz mono PCM16.
+fn wav_pcm_data(bytes: &[u8]) -> Result<&[u8], String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a RIFF/WAVE file".to_string());
+    }
+
+    let mut format_ok = false;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes([
+            bytes[pos + 4],
+            bytes[pos + 5],
+            bytes[pos + 6],
+            bytes[pos + 7],
+        ]) as usize;
+        let body = &bytes[pos + 8..(pos + 8 + size).min(bytes.len())];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let format = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                // 1 = PCM, 0xFFFE = WAVE_FORMAT_EXTENSIBLE
+                if (format != 1 && format != 0xFFFE)
+                    || channels != 1
+                    || rate != SAMPLE_RATE
+                    || bits != 16
+                {
+                    return Err(format!(
+                        "expected 16kHz mono PCM16, got format {} / {} ch / {} Hz / {} bit \
+                         (convert with: ffmpeg -i in.wav -ar 16000 -ac 1 -c:a pcm_s16le out.wav)",
+                        format, channels, rate, bits
+                    ));
+                }
+                format_ok = true;
+            }
+            b"data" => {
+                if !format_ok {
+                    return Err("data chunk before fmt chunk".to_string());
+                }
+                return Ok(body);
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        pos += 8 + size + (size & 1);
+    }
+    Err("no data chunk".to_string())
+}
+
+/// Look for `<stem>.json` next to the recording.
+fn read_sidecar(path: &Path) -> Result<Option<Sidecar>, Box<dyn std::error::Error>> {
+    let sidecar_path = path.with_extension("json");
+    if !sidecar_path.exists() {
+        return Ok(None);
+    }
+    let value: Value = serde_json::from_str(&fs::read_to_string(&sidecar_path)?)
+        .map_err(|e| format!("{}: {}", sidecar_path.display(), e))?;
+    Ok(Some(Sidecar {
+        wake: value["wake"].as_bool().unwrap_or(false),
+        wake_onset_s: value["wake_onset_s"].as_f64(),
+    }))
+}
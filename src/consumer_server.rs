@@ -1,12 +1,28 @@
-use crate::audio_source::{AudioCapture, AudioCaptureConfig};
+use crate::audio_codec::{AudioCodec, AudioEncoder};
+use crate::audio_source::{AudioCapture, AudioCaptureConfig, CaptureStatus, CHUNK_SIZE};
 use crate::mpv_controller::MpvController;
-use crate::protocol::{ConsumerConnection, ConsumerMessage, ProtocolError};
+use crate::protocol::{
+    hello_pending, ConsumerConnection, ConsumerMessage, ConsumerMessageType, ProtocolError,
+    Session, VadProbability, WakewordDetails, CAP_AUDIO_ADPCM, CAP_AUDIO_ULAW, CAP_CAPTURE_STATUS,
+    CAP_PREROLL, CAP_VAD_PROBABILITIES, CAP_WAKEWORD_DETAILS, DEFAULT_TOPICS, HANDSHAKE_TIMEOUT,
+    TOPIC_AUDIO, TOPIC_SPEECH, TOPIC_UTTERANCE, TOPIC_WAKEWORD,
+};
 use crate::spotify_controller::SpotifyController;
+use crate::wakeword_error::OpenWakeWordError;
+use crate::wakeword_gate::{ChannelPolicy, DetectionOverride, DetectionSettings, WakewordGate};
+use crate::wakeword_inference_gate::{rms_dbfs, InferenceGate, InferenceGateConfig};
+#[cfg(feature = "wakeword")]
 use crate::wakeword_model::Model as WakewordModel;
-use crate::wakeword_vad::{VadConfig, VadProcessor};
+use crate::wakeword_models::{validate_model_files, ModelRegistry};
+use crate::wakeword_vad::{
+    EndpointConfig, Endpointer, SpeechEvent, SpeechSegmenter, Utterance, VadConfig, VadProcessor,
+    VadWindow,
+};
 use crossbeam::channel::{Receiver, Sender};
+use std::collections::{HashMap, VecDeque};
 use std::net::{TcpListener, TcpStream};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -23,16 +39,66 @@ pub enum ConsumerServerError {
     #[error("Audio error: {0}")]
     Audio(String),
 
-    #[error("Consumer already connected")]
-    ConsumerAlreadyConnected,
+    #[error("Wakeword model error: {0}")]
+    Wakeword(#[from] OpenWakeWordError),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Too many consumers connected (max {0})")]
+    TooManyConsumers(usize),
 }
 
+/// Audio-detection pairs buffered per consumer before new ones are dropped
+const SUBSCRIBER_QUEUE_CAPACITY: usize = 20;
+
+/// Quiet chunks (10s) after which, with `--feature-snapshot`, the room tone
+/// replaces the synthetic warmup noise as the feature reset state
+const ROOM_TONE_CHUNKS: u64 = 125;
+
 /// Paired audio chunk with detection results
 #[derive(Debug, Clone)]
 pub struct AudioDetectionPair {
     pub audio_data: Vec<u8>,
     pub speech_detected: bool,
+    /// Speech probability of every VAD window completed by this chunk
+    pub vad_windows: Vec<VadWindow>,
+    /// Speech segment boundaries completed by this chunk
+    pub speech_events: Vec<SpeechEvent>,
     pub wakeword_event: Option<WakewordEvent>,
+    /// End of the utterance following the last wake word, if this chunk completed it
+    pub utterance: Option<Utterance>,
+    pub timestamp: u64,
+    /// Capture sample index of the first sample of this chunk
+    pub sample_index: u64,
+    /// Recent chunks up to and including this one; only filled when
+    /// `wakeword_event` is set and pre-roll is enabled
+    pub preroll: Vec<PrerollChunk>,
+}
+
+impl AudioDetectionPair {
+    /// Whether a consumer subscribed to `topics` gets any message from this pair
+    fn wanted_by(&self, topics: u32) -> bool {
+        topics & TOPIC_AUDIO != 0
+            || (topics & TOPIC_SPEECH != 0 && !self.speech_events.is_empty())
+            || (topics & TOPIC_WAKEWORD != 0 && self.wakeword_event.is_some())
+            || (topics & TOPIC_UTTERANCE != 0 && self.utterance.is_some())
+    }
+}
+
+/// Wall-clock estimate for `sample_index`, counting back from the capture
+/// timestamp of the chunk starting at `chunk_sample_index` (taken once its
+/// last sample arrived)
+fn timestamp_of(chunk_timestamp: u64, chunk_sample_index: u64, sample_index: u64) -> u64 {
+    let chunk_end = chunk_sample_index + CHUNK_SIZE as u64;
+    let samples_ago = chunk_end.saturating_sub(sample_index);
+    chunk_timestamp.saturating_sub(samples_ago * 1000 / 16000)
+}
+
+/// A buffered chunk with its original capture timestamp
+#[derive(Debug, Clone)]
+pub struct PrerollChunk {
+    pub audio_data: Vec<u8>,
     pub timestamp: u64,
 }
 
@@ -41,7 +107,16 @@ pub struct AudioDetectionPair {
 pub struct WakewordEvent {
     pub model: String,
     pub confidence: f32,
+    /// Threshold of `model` that was crossed.
+    pub threshold: f32,
     pub timestamp: u64,
+    /// Capture sample index of the first sample of the chunk that fired
+    pub sample_index: u64,
+    /// Recent scores of `model` on `channel`, oldest first, ending with the
+    /// firing score
+    pub scores: Vec<f32>,
+    /// Capture channel with the strongest score for `model`
+    pub channel: u32,
     pub spotify_was_paused: bool,
     pub mpv_was_paused: bool,
 }
@@ -52,8 +127,34 @@ pub struct ConsumerServerConfig {
     pub bind_address: String,
     pub audio_capture_config: AudioCaptureConfig,
     pub wakeword_models: Vec<String>,
-    pub detection_threshold: f32,
+    /// Capture channels scored for wake words, each by its own copy of the
+    /// models. Empty scores only the streamed channel.
+    pub wakeword_channels: Vec<u32>,
+    /// How the channels in `wakeword_channels` combine into one detection
+    pub channel_policy: ChannelPolicy,
+    /// Directory containing wake word and feature (`.tflite`) models.
+    pub wakeword_model_dir: String,
+    /// File caching the feature extractor's warmed-up state, so startup and
+    /// reloads skip the warmup inference (`None` always warms up).
+    pub feature_snapshot: Option<String>,
+    /// Threshold, debounce and refractory time for models without an override.
+    pub detection: DetectionSettings,
+    /// Use each model's manifest threshold, when it has one, instead of
+    /// `detection.threshold`.
+    pub manifest_thresholds: bool,
+    /// Per-model detection overrides, keyed by model name.
+    pub model_detection: HashMap<String, DetectionOverride>,
+    /// Milliseconds of audio ending with the firing chunk that is re-sent to
+    /// the consumer as a `PrerollAudio` burst after each wake word. 0 disables.
+    pub preroll_ms: u64,
+    /// Maximum number of simultaneously connected consumers
+    pub max_consumers: usize,
     pub vad_config: VadConfig,
+    /// Skip embedding and wake word inference during sustained silence
+    /// (`None` runs every model on every chunk)
+    pub inference_gate: Option<InferenceGateConfig>,
+    /// Timeouts for the `UtteranceComplete` event after each wake word
+    pub endpoint: EndpointConfig,
     /// `host:port` of the LED controller's HTTP API. The detection thread POSTs
     /// a `ww_detected` event here the instant a wake word fires, before any
     /// media-pause work, for the lowest-latency ring feedback.
@@ -69,21 +170,340 @@ impl Default for ConsumerServerConfig {
             bind_address: "127.0.0.1:8080".to_string(),
             audio_capture_config: AudioCaptureConfig::default(),
             wakeword_models: vec!["hey_mycroft".to_string()],
-            detection_threshold: 0.5,
+            wakeword_channels: Vec::new(),
+            channel_policy: ChannelPolicy::default(),
+            wakeword_model_dir: "models".to_string(),
+            feature_snapshot: None,
+            detection: DetectionSettings::default(),
+            manifest_thresholds: true,
+            model_detection: HashMap::new(),
+            preroll_ms: 0,
+            max_consumers: 8,
             vad_config: VadConfig::default(),
+            inference_gate: None,
+            endpoint: EndpointConfig::default(),
             led_endpoint: "127.0.0.1:3000".to_string(),
             spotify_endpoint: "127.0.0.1:3001".to_string(),
         }
     }
 }
 
-/// Consumer server that provides audio stream + events to a single consumer
+impl ConsumerServerConfig {
+    /// Capture channels the wake word models run on, in scoring order
+    fn scored_channels(&self) -> Vec<u32> {
+        if self.wakeword_channels.is_empty() {
+            vec![self.audio_capture_config.channel]
+        } else {
+            self.wakeword_channels.clone()
+        }
+    }
+}
+
+/// Stand-in for the TFLite model in builds without the `wakeword` feature.
+/// It can never be constructed, so the detection thread passes audio through
+/// without wake word events and reloads fail with a configuration error.
+#[cfg(not(feature = "wakeword"))]
+enum WakewordModel {}
+
+#[cfg(not(feature = "wakeword"))]
+impl WakewordModel {
+    fn new_with_feature_snapshot(
+        _wakeword_models: Vec<String>,
+        _class_mapping_dicts: Vec<HashMap<String, String>>,
+        _model_dir: &str,
+        _feature_snapshot: Option<&Path>,
+    ) -> Result<Self, OpenWakeWordError> {
+        Err(OpenWakeWordError::ConfigurationError(
+            "wake word detection needs the `wakeword` feature".to_string(),
+        ))
+    }
+
+    fn predict(
+        &mut self,
+        _x: &[i16],
+        _threshold: Option<HashMap<String, f32>>,
+        _debounce_time: f32,
+    ) -> Result<HashMap<String, f32>, OpenWakeWordError> {
+        match *self {}
+    }
+
+    fn skip(&mut self, _x: &[i16]) -> Result<HashMap<String, f32>, OpenWakeWordError> {
+        match *self {}
+    }
+
+    fn capture_reset_state(
+        &mut self,
+        _snapshot_path: Option<&Path>,
+    ) -> Result<(), OpenWakeWordError> {
+        match *self {}
+    }
+
+    fn prediction_buffer(&self, _model: &str) -> Option<&VecDeque<f32>> {
+        match *self {}
+    }
+
+    fn get_model_inputs(&self) -> &HashMap<String, usize> {
+        match *self {}
+    }
+}
+
+/// Wakeword models and detection gate shared by the detection thread and the
+/// reload path. Locks are always taken models first, then gate.
+#[derive(Clone)]
+struct WakewordState {
+    /// One model per scored channel, in `scored_channels` order; empty until
+    /// loaded
+    models: Arc<Mutex<Vec<WakewordModel>>>,
+    gate: Arc<Mutex<WakewordGate>>,
+    /// Serializes reloads so two requests can't interleave their swaps
+    reload_lock: Arc<Mutex<()>>,
+}
+
+impl WakewordState {
+    fn new(config: &ConsumerServerConfig) -> Self {
+        Self {
+            models: Arc::new(Mutex::new(Vec::new())),
+            gate: Arc::new(Mutex::new(WakewordGate::new(config.detection, HashMap::new()))),
+            reload_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Build a model per scored channel and a gate for `models` without
+    /// touching the running ones
+    fn build(
+        config: &ConsumerServerConfig,
+        models: &[String],
+        overrides: &HashMap<String, DetectionOverride>,
+    ) -> Result<(Vec<WakewordModel>, WakewordGate), ConsumerServerError> {
+        validate_model_files(models, &config.wakeword_model_dir)?;
+        let registry = ModelRegistry::discover(&config.wakeword_model_dir)?;
+        let channel_models = config
+            .scored_channels()
+            .iter()
+            .map(|_| {
+                WakewordModel::new_with_feature_snapshot(
+                    models.to_vec(),
+                    vec![],
+                    &config.wakeword_model_dir,
+                    config.feature_snapshot.as_deref().map(Path::new),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let settings = registry.detection_settings(
+            models,
+            &config.detection,
+            config.manifest_thresholds,
+            overrides,
+        );
+        for (name, s) in &settings {
+            log::info!(
+                "🎯 Detection settings for '{}': threshold={:.2} debounce={}ms refractory={}ms",
+                name,
+                s.threshold,
+                s.debounce_ms,
+                s.refractory_ms
+            );
+        }
+        let gate = WakewordGate::new(config.detection, settings);
+        Ok((channel_models, gate))
+    }
+
+    /// Load the configured models if none are loaded yet
+    fn ensure_loaded(&self, config: &ConsumerServerConfig) -> Result<(), ConsumerServerError> {
+        let mut models_guard = self.models.lock().unwrap();
+        if !models_guard.is_empty() {
+            return Ok(());
+        }
+        if cfg!(not(feature = "wakeword")) {
+            log::warn!("⚠️  Built without the `wakeword` feature: audio passes through without wake word detection");
+            return Ok(());
+        }
+
+        log::info!(
+            "🎯 Loading wakeword models {:?} from '{}' for channels {:?} (policy {})",
+            config.wakeword_models,
+            config.wakeword_model_dir,
+            config.scored_channels(),
+            config.channel_policy
+        );
+        let (models, gate) = Self::build(config, &config.wakeword_models, &config.model_detection)?;
+        *self.gate.lock().unwrap() = gate;
+        *models_guard = models;
+        log::info!(
+            "✅ Wakeword model loaded with {} wake models",
+            config.wakeword_models.len()
+        );
+        Ok(())
+    }
+
+    /// Build new models in the calling thread and swap them in. The detection
+    /// thread keeps running on the old models until the swap, which only holds
+    /// the locks for the assignment. Empty `models`/`detection` fall back to
+    /// the service configuration. Returns a summary of what was loaded.
+    fn reload(
+        &self,
+        config: &ConsumerServerConfig,
+        models: &[String],
+        detection: &[String],
+    ) -> Result<String, ConsumerServerError> {
+        let _reload_guard = self.reload_lock.lock().unwrap();
+
+        let models = if models.is_empty() {
+            config.wakeword_models.clone()
+        } else {
+            models.to_vec()
+        };
+        let overrides = if detection.is_empty() {
+            config.model_detection.clone()
+        } else {
+            detection
+                .iter()
+                .map(|spec| DetectionOverride::parse(spec))
+                .collect::<Result<HashMap<_, _>, _>>()
+                .map_err(ConsumerServerError::Config)?
+        };
+
+        log::info!("🔄 Reloading wakeword models {:?}", models);
+        let build_start = Instant::now();
+        let (channel_models, gate) = Self::build(config, &models, &overrides)?;
+
+        let summary = channel_models[0]
+            .get_model_inputs()
+            .keys()
+            .map(|name| format!("{} (threshold {:.2})", name, gate.settings(name).threshold))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let old_models = {
+            let mut models_guard = self.models.lock().unwrap();
+            *self.gate.lock().unwrap() = gate;
+            std::mem::replace(&mut *models_guard, channel_models)
+        };
+        // Free the old interpreters outside the lock
+        drop(old_models);
+
+        log::info!(
+            "✅ Wakeword models reloaded in {:.1}ms: {}",
+            build_start.elapsed().as_secs_f64() * 1000.0,
+            summary
+        );
+        Ok(summary)
+    }
+}
+
+/// One connected consumer's queue of audio-detection pairs
+struct Subscriber {
+    id: u64,
+    addr: String,
+    sender: Sender<AudioDetectionPair>,
+    /// Pairs dropped because this consumer's queue was full
+    dropped: Arc<AtomicU64>,
+    topics: Arc<AtomicU32>,
+    notices: Sender<ConsumerMessage>,
+}
+
+/// A consumer's end of its subscription
+struct Subscription {
+    id: u64,
+    receiver: Receiver<AudioDetectionPair>,
+    dropped: Arc<AtomicU64>,
+    /// TOPIC_* flags, updated by the consumer's `Subscribe` requests
+    topics: Arc<AtomicU32>,
+    /// Service status messages for every consumer, e.g. capture device loss
+    notices: Receiver<ConsumerMessage>,
+}
+
+/// Fans the detection stream out to every connected consumer. Each consumer
+/// has its own bounded queue, so a slow one only loses its own pairs.
+#[derive(Clone, Default)]
+struct Subscribers {
+    inner: Arc<Mutex<Vec<Subscriber>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Subscribers {
+    /// Register a consumer with the default topics
+    fn subscribe(&self, addr: &str) -> Subscription {
+        let (sender, receiver) = crossbeam::channel::bounded(SUBSCRIBER_QUEUE_CAPACITY);
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let topics = Arc::new(AtomicU32::new(DEFAULT_TOPICS));
+        let (notice_sender, notices) = crossbeam::channel::bounded(SUBSCRIBER_QUEUE_CAPACITY);
+        self.inner.lock().unwrap().push(Subscriber {
+            id,
+            addr: addr.to_string(),
+            sender,
+            dropped: Arc::clone(&dropped),
+            topics: Arc::clone(&topics),
+            notices: notice_sender,
+        });
+        Subscription {
+            id,
+            receiver,
+            dropped,
+            topics,
+            notices,
+        }
+    }
+
+    fn unsubscribe(&self, id: u64) {
+        self.inner.lock().unwrap().retain(|s| s.id != id);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.lock().unwrap().len()
+    }
+
+    /// Queue `message` for every consumer regardless of topics, without
+    /// blocking; a consumer with a full notice queue misses it
+    fn notify(&self, message: ConsumerMessage) {
+        for subscriber in self.inner.lock().unwrap().iter() {
+            let _ = subscriber.notices.try_send(message.clone());
+        }
+    }
+
+    /// Offer `pair` to every consumer subscribed to something in it, without
+    /// blocking. Full queues count a drop for that consumer; disconnected ones
+    /// are removed.
+    fn broadcast(&self, pair: AudioDetectionPair) {
+        let mut subscribers = self.inner.lock().unwrap();
+        subscribers.retain(|subscriber| {
+            if !pair.wanted_by(subscriber.topics.load(Ordering::Relaxed)) {
+                return true;
+            }
+            match subscriber.sender.try_send(pair.clone()) {
+                Ok(()) => true,
+                Err(crossbeam::channel::TrySendError::Full(_)) => {
+                    let dropped = subscriber.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    if dropped % 10 == 0 {
+                        log::warn!(
+                            "⚠️ [Detection] Backpressure: dropped {} audio pairs for consumer {}, consumer lagging",
+                            dropped,
+                            subscriber.addr
+                        );
+                    }
+                    true
+                }
+                Err(crossbeam::channel::TrySendError::Disconnected(_)) => {
+                    log::debug!(
+                        "🔌 Detection thread: consumer {} disconnected during send",
+                        subscriber.addr
+                    );
+                    false
+                }
+            }
+        });
+    }
+}
+
+/// Consumer server that provides audio stream + events to every connected consumer
 pub struct ConsumerServer {
     config: ConsumerServerConfig,
     should_stop: Arc<AtomicBool>,
-    consumer_connected: Arc<AtomicBool>,
+    subscribers: Subscribers,
     audio_capture: Arc<Mutex<Option<AudioCapture>>>,
-    wakeword_model: Arc<Mutex<Option<WakewordModel>>>,
+    wakeword: WakewordState,
     vad_processor: Arc<Mutex<Option<VadProcessor>>>,
     spotify_controller: SpotifyController,
     mpv_controller: MpvController,
@@ -93,12 +513,13 @@ pub struct ConsumerServer {
 impl ConsumerServer {
     pub fn new(config: ConsumerServerConfig) -> Self {
         let spotify_controller = SpotifyController::new(config.spotify_endpoint.clone());
+        let wakeword = WakewordState::new(&config);
         Self {
             config,
             should_stop: Arc::new(AtomicBool::new(false)),
-            consumer_connected: Arc::new(AtomicBool::new(false)),
+            subscribers: Subscribers::default(),
             audio_capture: Arc::new(Mutex::new(None)),
-            wakeword_model: Arc::new(Mutex::new(None)),
+            wakeword,
             vad_processor: Arc::new(Mutex::new(None)),
             spotify_controller,
             mpv_controller: MpvController::new(),
@@ -111,16 +532,31 @@ impl ConsumerServer {
         self.barge_in_tx = Some(tx);
     }
 
-    /// Start the detection thread and return the receiver for audio-detection pairs
-    fn start_detection_thread(&self) -> Result<Receiver<AudioDetectionPair>, ConsumerServerError> {
-        let capacity = 20;
-        let (sender, receiver) = crossbeam::channel::bounded(capacity);
+    /// Pre-load the wakeword models before accepting connections so a missing
+    /// or broken model fails at startup instead of in the detection thread
+    pub fn initialize_wakeword_model(&self) -> Result<(), ConsumerServerError> {
+        self.wakeword.ensure_loaded(&self.config)
+    }
+
+    /// Replace the running wakeword models without restarting capture or
+    /// playback. `models` and `detection` (`MODEL:key=value,...` overrides)
+    /// default to the service configuration when empty. Blocks while the new
+    /// models load; detection continues on the old ones meanwhile.
+    pub fn reload_wakeword(
+        &self,
+        models: &[String],
+        detection: &[String],
+    ) -> Result<String, ConsumerServerError> {
+        self.wakeword.reload(&self.config, models, detection)
+    }
 
+    /// Start the detection thread, which fans its output out to all subscribers
+    fn start_detection_thread(&self) {
         // Clone resources for detection thread
         let should_stop = Arc::clone(&self.should_stop);
-        let consumer_connected = Arc::clone(&self.consumer_connected);
+        let subscribers = self.subscribers.clone();
         let audio_capture = Arc::clone(&self.audio_capture);
-        let wakeword_model = Arc::clone(&self.wakeword_model);
+        let wakeword = self.wakeword.clone();
         let vad_processor = Arc::clone(&self.vad_processor);
         let config = self.config.clone();
         let spotify_controller = self.spotify_controller.clone();
@@ -131,12 +567,11 @@ impl ConsumerServer {
         thread::spawn(move || {
             let result = Self::detection_thread(
                 should_stop,
-                consumer_connected,
+                subscribers,
                 audio_capture,
-                wakeword_model,
+                wakeword,
                 vad_processor,
                 config,
-                sender,
                 spotify_controller,
                 mpv_controller,
                 barge_in_tx,
@@ -146,8 +581,6 @@ impl ConsumerServer {
                 log::error!("❌ Detection thread failed: {}", e);
             }
         });
-
-        Ok(receiver)
     }
 
     /// Start the consumer server (blocking)
@@ -158,7 +591,7 @@ impl ConsumerServer {
         );
 
         // Start detection thread first (runs independently)
-        let detection_receiver = self.start_detection_thread()?;
+        self.start_detection_thread();
         log::info!("✅ Detection thread started");
 
         let listener = TcpListener::bind(&self.config.bind_address)?;
@@ -176,15 +609,16 @@ impl ConsumerServer {
                 Ok((stream, addr)) => {
                     log::info!("🎯 Consumer connection attempt from {}", addr);
 
-                    // Check if we already have a consumer
-                    if self.consumer_connected.load(Ordering::SeqCst) {
-                        log::warn!("⚠️  Rejecting consumer from {}: already connected", addr);
-                        self.reject_consumer(stream, "Consumer already connected".to_string());
+                    if self.subscribers.len() >= self.config.max_consumers {
+                        let error =
+                            ConsumerServerError::TooManyConsumers(self.config.max_consumers);
+                        log::warn!("⚠️  Rejecting consumer from {}: {}", addr, error);
+                        self.reject_consumer(stream, error.to_string());
                         continue;
                     }
 
                     // Handle the consumer connection
-                    self.handle_consumer(stream, addr.to_string(), &detection_receiver);
+                    self.handle_consumer(stream, addr.to_string());
                 }
                 Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                     // No connection available, sleep and continue
@@ -220,12 +654,11 @@ impl ConsumerServer {
     /// Detection thread that processes audio and generates detection events
     fn detection_thread(
         should_stop: Arc<AtomicBool>,
-        consumer_connected: Arc<AtomicBool>,
+        subscribers: Subscribers,
         audio_capture: Arc<Mutex<Option<AudioCapture>>>,
-        wakeword_model: Arc<Mutex<Option<WakewordModel>>>,
+        wakeword: WakewordState,
         vad_processor: Arc<Mutex<Option<VadProcessor>>>,
         config: ConsumerServerConfig,
-        sender: Sender<AudioDetectionPair>,
         spotify_controller: SpotifyController,
         mpv_controller: MpvController,
         barge_in_tx: Option<Sender<()>>,
@@ -238,7 +671,13 @@ impl ConsumerServer {
                     "🎤 Initializing audio capture for streaming (channel {})",
                     config.audio_capture_config.channel
                 );
-                match AudioCapture::new(config.audio_capture_config.clone()) {
+                let mut capture_config = config.audio_capture_config.clone();
+                // Other channels than the streamed one need the full frame
+                capture_config.all_channels |= !config.wakeword_channels.is_empty();
+                capture_config
+                    .required_channels
+                    .extend(&config.wakeword_channels);
+                match AudioCapture::new(capture_config) {
                     Ok(capture) => {
                         *capture_guard = Some(capture);
                     }
@@ -250,31 +689,7 @@ impl ConsumerServer {
         }
 
         // Initialize wakeword model
-        {
-            let mut model_guard = wakeword_model.lock().unwrap();
-            if model_guard.is_none() {
-                log::info!("🎯 Initializing wakeword model for detection");
-                match WakewordModel::new_with_model_path(
-                    config.wakeword_models.clone(),
-                    vec![],
-                    "models",
-                ) {
-                    Ok(model) => {
-                        *model_guard = Some(model);
-                        log::info!(
-                            "✅ Wakeword model loaded with {} wake models",
-                            config.wakeword_models.len()
-                        );
-                    }
-                    Err(e) => {
-                        return Err(ConsumerServerError::Audio(format!(
-                            "Wakeword model error: {}",
-                            e
-                        )));
-                    }
-                }
-            }
-        }
+        wakeword.ensure_loaded(&config)?;
 
         // Initialize VAD processor
         {
@@ -317,36 +732,90 @@ impl ConsumerServer {
 
         log::info!("🎵 Starting audio detection processing");
 
-        let mut last_wakeword_time: Option<Instant> = None;
+        let scored_channels = config.scored_channels();
+        let mut missing_channel_warned = false;
+        // Consecutive chunks without speech; room tone is captured once
+        let mut quiet_chunks = 0u64;
+        let mut room_tone_captured = config.feature_snapshot.is_none();
         let mut detection_attempts = 0u64;
         let mut audio_chunks_processed = 0u64;
         let start_time = Instant::now();
-        let mut dropped_pairs = 0u64;
-
-        const WAKEWORD_DEBOUNCE_MS: u64 = 3000;
 
         // Near-miss instrumentation: when WW_NEARMISS_LOG=1, track the peak wake
-        // confidence across each VAD speech segment and log it when the segment
-        // ends without firing. This surfaces sub-threshold "Hey Mycroft" misses
+        // confidence (and VAD probability) across each VAD speech segment and log
+        // it when the segment ends without firing. This surfaces sub-threshold "Hey Mycroft" misses
         // that are otherwise invisible (only >= threshold detections are logged).
         let nearmiss_log = std::env::var("WW_NEARMISS_LOG")
             .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
             .unwrap_or(false);
         let mut segment_peak_conf = 0.0f32;
+        let mut segment_peak_vad = 0.0f32;
         let mut in_speech_segment = false;
         let mut segment_fired = false;
         let mut silence_run = 0u32;
+        // Turns VAD windows into SpeechStarted/SpeechStopped boundaries
+        let mut segmenter = SpeechSegmenter::new(&config.vad_config);
+        // Skips the expensive models in a silent room, if enabled
+        let mut inference_gate = config.inference_gate.as_ref().map(InferenceGate::new);
+        // Finds where the user stopped talking after each wake word
+        let mut endpointer = Endpointer::new(&config.endpoint, &config.vad_config);
         // End a speech segment after this many consecutive non-speech chunks, so
         // brief VAD flicker mid-utterance doesn't split one phrase into several.
         const SEGMENT_SILENCE_CHUNKS: u32 = 25;
 
+        // Pre-roll ring of the most recent chunks, replayed to the consumer on
+        // wake word so STT gets the phrase and the speech leading into it.
+        let chunk_ms = (CHUNK_SIZE as u64 * 1000) / 16000;
+        let preroll_chunks = config.preroll_ms.div_ceil(chunk_ms) as usize;
+        let mut preroll_ring: VecDeque<PrerollChunk> = VecDeque::with_capacity(preroll_chunks);
+        if preroll_chunks > 0 {
+            log::info!(
+                "🎞️ Pre-roll enabled: {} chunks ({}ms)",
+                preroll_chunks,
+                preroll_chunks as u64 * chunk_ms
+            );
+        }
+
         while !should_stop.load(Ordering::SeqCst) {
-            let audio = {
+            let (audio, status) = {
                 let capture_guard = audio_capture.lock().unwrap();
-                capture_guard.as_ref().and_then(|c| c.try_next_chunk())
+                let capture = capture_guard.as_ref();
+                (
+                    capture.and_then(|c| c.try_next_chunk()),
+                    capture.and_then(|c| c.try_next_status()),
+                )
             };
 
-            if let Some(ref chunk_data) = audio {
+            // The capture thread reopens the device by itself; consumers
+            // just learn why audio stopped and how long the gap was
+            match status {
+                Some(CaptureStatus::Lost(reason)) => {
+                    log::warn!("🎤 [Detection] Audio capture lost: {}", reason);
+                    subscribers.notify(ConsumerMessage::CaptureStatus {
+                        lost: true,
+                        deaf_ms: 0,
+                        reason,
+                    });
+                }
+                Some(CaptureStatus::Restored { deaf_for }) => {
+                    log::info!(
+                        "🎤 [Detection] Audio capture resumed after {:.1}s",
+                        deaf_for.as_secs_f64()
+                    );
+                    subscribers.notify(ConsumerMessage::CaptureStatus {
+                        lost: false,
+                        deaf_ms: deaf_for.as_millis() as u64,
+                        reason: String::new(),
+                    });
+                }
+                None => {}
+            }
+
+            if let Some(ref chunk) = audio {
+                let chunk_data = &chunk.data;
+                // Stamp the chunk as soon as it leaves the capture ring, before
+                // any inference, so timestamps reflect capture time.
+                let capture_timestamp = ConsumerMessage::current_timestamp();
                 let samples: Vec<i16> = chunk_data
                     .chunks_exact(2)
                     .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
@@ -355,6 +824,16 @@ impl ConsumerServer {
                 if !samples.is_empty() {
                     audio_chunks_processed += 1;
 
+                    if preroll_chunks > 0 {
+                        if preroll_ring.len() == preroll_chunks {
+                            preroll_ring.pop_front();
+                        }
+                        preroll_ring.push_back(PrerollChunk {
+                            audio_data: chunk_data.clone(),
+                            timestamp: capture_timestamp,
+                        });
+                    }
+
                     // Feed each chunk directly to the stateful model exactly once.
                     // The model's preprocessor maintains internal mel/embedding buffers
                     // across calls, so re-feeding overlapping windows would corrupt state.
@@ -364,51 +843,144 @@ impl ConsumerServer {
                         if detection_attempts % 100 == 0 {
                             let elapsed = start_time.elapsed();
                             log::debug!(
-                                "📊 [Detection] Performance stats: {} detections in {:.1}s, {} audio chunks, rate={:.1} detections/min, dropped={}",
+                                "📊 [Detection] Performance stats: {} detections in {:.1}s, {} audio chunks, rate={:.1} detections/min, consumers={}",
                                 detection_attempts,
                                 elapsed.as_secs_f64(),
                                 audio_chunks_processed,
                                 (detection_attempts as f64) / elapsed.as_secs_f64() * 60.0,
-                                dropped_pairs
+                                subscribers.len()
                             );
                         }
-
-                        let (event_opt, ww_max_conf) =
-                            Self::process_wakeword_detection_standalone(
-                                &wakeword_model,
-                                &samples,
-                                config.detection_threshold,
-                                &last_wakeword_time,
-                                WAKEWORD_DEBOUNCE_MS,
-                                &spotify_controller,
-                                &mpv_controller,
-                                &barge_in_tx,
-                                &config.led_endpoint,
-                            )?;
-                        let event = match event_opt {
-                            Some((event, at)) => {
-                                last_wakeword_time = Some(at);
-                                Some(event)
+                        // About once a minute, report how much inference the gate saved
+                        if detection_attempts.is_multiple_of(750) {
+                            if let Some(ref gate) = inference_gate {
+                                let stats = gate.stats();
+                                log::info!(
+                                    "📊 [Detection] Inference gate: {} chunks run, {} skipped ({:.0}% skipped)",
+                                    stats.run,
+                                    stats.skipped,
+                                    stats.skipped_ratio() * 100.0
+                                );
                             }
-                            None => None,
+                        }
+
+                        let channel_samples: Vec<&[i16]> = if config.wakeword_channels.is_empty() {
+                            vec![samples.as_slice()]
+                        } else {
+                            // Capture only opens devices with every scored
+                            // channel, but score the streamed one rather than
+                            // nothing if a chunk ever lacks one
+                            scored_channels
+                                .iter()
+                                .map(|&channel| match chunk.channels.get(channel as usize) {
+                                    Some(samples) => samples.as_slice(),
+                                    None => {
+                                        if !missing_channel_warned {
+                                            missing_channel_warned = true;
+                                            log::warn!(
+                                                "⚠️ [Detection] Wake word channel {} missing from capture ({} channels), scoring channel {} instead",
+                                                channel,
+                                                chunk.channels.len(),
+                                                config.audio_capture_config.channel
+                                            );
+                                        }
+                                        samples.as_slice()
+                                    }
+                                })
+                                .collect()
                         };
-                        (event, ww_max_conf)
+
+                        // The VAD result for this chunk isn't known yet; an open
+                        // speech segment keeps the gate open like a loud chunk
+                        // does. A wake word on any scored mic must open it, so
+                        // it hears the loudest one.
+                        let run_inference = inference_gate.as_mut().is_none_or(|gate| {
+                            let loudest = channel_samples
+                                .iter()
+                                .map(|&s| (rms_dbfs(s), s))
+                                .max_by(|a, b| a.0.total_cmp(&b.0))
+                                .map_or(samples.as_slice(), |(_, s)| s);
+                            gate.should_run(loudest, segmenter.in_speech())
+                        });
+
+                        Self::process_wakeword_detection_standalone(
+                            &wakeword,
+                            &channel_samples,
+                            &scored_channels,
+                            config.channel_policy,
+                            start_time.elapsed(),
+                            chunk.sample_index,
+                            &spotify_controller,
+                            &mpv_controller,
+                            &barge_in_tx,
+                            &config.led_endpoint,
+                            run_inference,
+                        )?
                     };
 
-                    let speech_detected = {
+                    let vad_windows = {
                         let mut vad_guard = vad_processor.lock().unwrap();
                         if let Some(ref mut vad) = vad_guard.as_mut() {
-                            match vad.analyze_chunk(chunk_data) {
-                                Ok(has_speech) => has_speech,
+                            match vad.analyze_windows(chunk_data, chunk.sample_index) {
+                                Ok(windows) => windows,
                                 Err(e) => {
                                     log::warn!("⚠️ VAD processing error: {}", e);
-                                    false
+                                    Vec::new()
                                 }
                             }
                         } else {
-                            false
+                            Vec::new()
                         }
                     };
+                    let speech_detected = vad_windows.iter().any(|w| w.has_speech);
+                    let speech_events: Vec<SpeechEvent> = vad_windows
+                        .iter()
+                        .filter_map(|window| segmenter.push(window))
+                        .collect();
+
+                    if speech_detected || segmenter.in_speech() || wakeword_event.is_some() {
+                        quiet_chunks = 0;
+                    } else {
+                        quiet_chunks += 1;
+                    }
+                    if !room_tone_captured && quiet_chunks >= ROOM_TONE_CHUNKS {
+                        room_tone_captured = true;
+                        let snapshot_path = config.feature_snapshot.as_deref().map(Path::new);
+                        let mut models = wakeword.models.lock().unwrap();
+                        // Every channel's model keeps its own room tone; the
+                        // file holds the first one for the next start
+                        for (i, model) in models.iter_mut().enumerate() {
+                            let path = snapshot_path.filter(|_| i == 0);
+                            match model.capture_reset_state(path) {
+                                Ok(()) if i == 0 => log::info!(
+                                    "💾 [Detection] Captured {}s of room tone as the feature reset state",
+                                    ROOM_TONE_CHUNKS * CHUNK_SIZE as u64 / 16000
+                                ),
+                                Ok(()) => {}
+                                Err(e) => log::warn!(
+                                    "⚠️ [Detection] Could not capture room tone feature state: {}",
+                                    e
+                                ),
+                            }
+                        }
+                    }
+
+                    // The wake phrase itself ends with this chunk; listen from there
+                    if wakeword_event.is_some() {
+                        endpointer.start(chunk.sample_index + CHUNK_SIZE as u64);
+                    }
+                    let utterance = vad_windows
+                        .iter()
+                        .find_map(|window| endpointer.push(window));
+                    if let Some(ref u) = utterance {
+                        log::info!(
+                            "🗣️ [Detection] Utterance complete ({:?}): samples {}..{} ({}ms)",
+                            u.reason,
+                            u.start_sample,
+                            u.end_sample,
+                            (u.end_sample - u.start_sample) * 1000 / 16000
+                        );
+                    }
 
                     if nearmiss_log {
                         if speech_detected {
@@ -417,6 +989,10 @@ impl ConsumerServer {
                             if last_ww_max_conf > segment_peak_conf {
                                 segment_peak_conf = last_ww_max_conf;
                             }
+                            segment_peak_vad = vad_windows
+                                .iter()
+                                .map(|w| w.probability)
+                                .fold(segment_peak_vad, f32::max);
                             if wakeword_event.is_some() {
                                 segment_fired = true;
                             }
@@ -425,42 +1001,42 @@ impl ConsumerServer {
                             if silence_run >= SEGMENT_SILENCE_CHUNKS {
                                 if !segment_fired {
                                     log::info!(
-                                        "🔎 [WW-NEARMISS] speech segment ended without firing: peak_confidence={:.3} (threshold={:.2})",
+                                        "🔎 [WW-NEARMISS] speech segment ended without firing: peak_confidence={:.3} (threshold={:.2}), peak_vad={:.3}",
                                         segment_peak_conf,
-                                        config.detection_threshold
+                                        config.detection.threshold,
+                                        segment_peak_vad
                                     );
                                 }
                                 in_speech_segment = false;
                                 segment_peak_conf = 0.0;
+                                segment_peak_vad = 0.0;
                                 segment_fired = false;
                                 silence_run = 0;
                             }
                         }
                     }
 
+                    let preroll = if wakeword_event.is_some() {
+                        preroll_ring.iter().cloned().collect()
+                    } else {
+                        Vec::new()
+                    };
+
                     let pair = AudioDetectionPair {
                         audio_data: chunk_data.clone(),
                         speech_detected,
+                        vad_windows,
+                        speech_events,
                         wakeword_event,
-                        timestamp: ConsumerMessage::current_timestamp(),
+                        utterance,
+                        timestamp: capture_timestamp,
+                        sample_index: chunk.sample_index,
+                        preroll,
                     };
 
-                    if consumer_connected.load(Ordering::SeqCst) {
-                        match sender.try_send(pair) {
-                            Ok(()) => {}
-                            Err(crossbeam::channel::TrySendError::Full(_)) => {
-                                dropped_pairs += 1;
-                                if dropped_pairs % 10 == 0 {
-                                    log::warn!("⚠️ [Detection] Backpressure: dropped {} audio pairs, consumer lagging", dropped_pairs);
-                                }
-                            }
-                            Err(crossbeam::channel::TrySendError::Disconnected(_)) => {
-                                log::debug!(
-                                    "🔌 Detection thread: consumer disconnected during send"
-                                );
-                            }
-                        }
-                    }
+                    // Barge-in and media pause already ran once above; every
+                    // consumer just gets its own copy of the result.
+                    subscribers.broadcast(pair);
                 }
             } else {
                 thread::sleep(Duration::from_millis(10));
@@ -472,131 +1048,140 @@ impl ConsumerServer {
     }
 
     /// Process wakeword detection without consumer connection (standalone)
-    /// Returns `(Some((WakewordEvent, timestamp)), wake_peak)` if a wake fired,
-    /// else `(None, wake_peak)`. The peak confidence across models is always
+    /// Returns `(Some(WakewordEvent), wake_peak)` if a wake fired, else
+    /// `(None, wake_peak)`. The peak confidence across models is always
     /// returned so callers can log sub-threshold near-misses.
+    ///
+    /// `channel_samples` holds this chunk for each of `channels`, scored by
+    /// the model of the same index and combined according to `policy`.
     #[allow(clippy::too_many_arguments)]
     fn process_wakeword_detection_standalone(
-        wakeword_model: &Arc<Mutex<Option<WakewordModel>>>,
-        detection_samples: &[i16],
-        threshold: f32,
-        last_wakeword_time: &Option<Instant>,
-        debounce_ms: u64,
+        wakeword: &WakewordState,
+        channel_samples: &[&[i16]],
+        channels: &[u32],
+        policy: ChannelPolicy,
+        now: Duration,
+        sample_index: u64,
         spotify_controller: &SpotifyController,
         mpv_controller: &MpvController,
         barge_in_tx: &Option<Sender<()>>,
         led_endpoint: &str,
-    ) -> Result<(Option<(WakewordEvent, Instant)>, f32), ConsumerServerError> {
+        run_inference: bool,
+    ) -> Result<(Option<WakewordEvent>, f32), ConsumerServerError> {
         let mut max_conf = 0.0f32;
-        if let Some(ref mut model) = wakeword_model.lock().unwrap().as_mut() {
+        let mut models = wakeword.models.lock().unwrap();
+        if !models.is_empty() {
+            if !run_inference {
+                // Sustained silence: keep the mel buffer warm, nothing can fire
+                for (model, samples) in models.iter_mut().zip(channel_samples) {
+                    if let Err(e) = model.skip(samples) {
+                        log::warn!("[Detection] Wakeword feature update failed: {}", e);
+                    }
+                }
+                return Ok((None, max_conf));
+            }
+
             // Time the TFLite inference so we can tell, on-device, how much of
             // the end-to-end latency is the model itself vs. the pause work.
             let predict_start = Instant::now();
-            match model.predict(detection_samples, None, 1.0) {
-                Ok(predictions) => {
+            match Self::predict_channels(&mut models, channel_samples) {
+                Ok(per_channel) => {
                     let predict_ms = predict_start.elapsed().as_secs_f64() * 1000.0;
-                    // Check predictions against threshold.
-                    for (model_name, confidence) in predictions {
-                        if confidence > max_conf {
-                            max_conf = confidence;
-                        }
-                        if confidence >= threshold {
-                            let now = Instant::now();
-                            let should_debounce = if let Some(last_time) = last_wakeword_time {
-                                now.duration_since(*last_time).as_millis()
-                                    < debounce_ms as u128
-                            } else {
-                                false
-                            };
-
-                            if should_debounce {
-                                log::debug!(
-                                    "🔇 [Detection] Wake word '{}' debounced (confidence {:.6}) - last was {:.1}ms ago",
-                                    model_name,
-                                    confidence,
-                                    last_wakeword_time.unwrap().elapsed().as_millis()
-                                );
-                                continue;
-                            }
+                    let predictions = policy.combine(&per_channel);
+                    let (fired, peak) = wakeword.gate.lock().unwrap().select(&predictions, now);
+                    max_conf = peak;
 
-                            log::info!(
-                                "🎯 [Detection] WAKEWORD DETECTED: '{}' with confidence {:.6} (tflite inference {:.1}ms)",
-                                model_name,
-                                confidence,
-                                predict_ms
-                            );
+                    if let Some(fire) = fired {
+                        let strongest = ChannelPolicy::strongest_channel(&per_channel, &fire.model);
+                        log::info!(
+                            "🎯 [Detection] WAKEWORD DETECTED: '{}' with confidence {:.6} >= threshold {:.2} on channel {} (tflite inference {:.1}ms)",
+                            fire.model,
+                            fire.confidence,
+                            fire.threshold,
+                            channels[strongest],
+                            predict_ms
+                        );
 
-                            // --- Immediate feedback, BEFORE any blocking work ---
-                            // Light the ring instantly via the LED controller's
-                            // HTTP API (fire-and-forget, never blocks). This is
-                            // the lowest-latency feedback path: it does not wait
-                            // for the agent's TCP + STT round-trip.
-                            crate::led_notify::notify_ww_detected(led_endpoint);
-
-                            // Send barge-in signal to producer (automatic server-side barge-in)
-                            // Use try_send - non-blocking, stale signals will be drained by producer
-                            if let Some(ref barge_in) = barge_in_tx {
-                                match barge_in.try_send(()) {
-                                    Ok(()) => {
-                                        log::info!("🔥 Sent barge-in signal to producer (automatic interruption)");
-                                    }
-                                    Err(e) => {
-                                        log::debug!("Barge-in signal not sent (producer may not be playing): {}", e);
-                                    }
+                        // --- Immediate feedback, BEFORE any blocking work ---
+                        // Light the ring instantly via the LED controller's
+                        // HTTP API (fire-and-forget, never blocks). This is
+                        // the lowest-latency feedback path: it does not wait
+                        // for the agent's TCP + STT round-trip.
+                        crate::led_notify::notify_ww_detected(led_endpoint);
+
+                        // Send barge-in signal to producer (automatic server-side barge-in)
+                        // Use try_send - non-blocking, stale signals will be drained by producer
+                        if let Some(ref barge_in) = barge_in_tx {
+                            match barge_in.try_send(()) {
+                                Ok(()) => {
+                                    log::info!("🔥 Sent barge-in signal to producer (automatic interruption)");
+                                }
+                                Err(e) => {
+                                    log::debug!("Barge-in signal not sent (producer may not be playing): {}", e);
                                 }
                             }
+                        }
 
-                            // --- Pause Spotify + mpv concurrently ---
-                            // Each pause runs on its own thread so total latency
-                            // is max(spotify, mpv) instead of the sum. Both
-                            // controllers are cheap to clone (the Spotify one
-                            // caches its D-Bus connection internally).
-                            let pause_start = Instant::now();
-                            let spotify = spotify_controller.clone();
-                            let spotify_handle = thread::spawn(move || {
-                                let t = Instant::now();
-                                let paused = spotify.pause_for_wakeword();
-                                (paused, t.elapsed())
-                            });
-                            let mpv = mpv_controller.clone();
-                            let mpv_handle = thread::spawn(move || {
-                                let t = Instant::now();
-                                let paused = mpv.pause_for_wakeword();
-                                (paused, t.elapsed())
-                            });
-
-                            let (spotify_was_paused, spotify_dur) =
-                                spotify_handle.join().unwrap_or((false, Duration::ZERO));
-                            let (mpv_was_paused, mpv_dur) =
-                                mpv_handle.join().unwrap_or((false, Duration::ZERO));
-
-                            log::info!(
-                                "⏱️ [Detection] media pause done in {:.1}ms (spotify {:.1}ms was_paused={}, mpv {:.1}ms was_paused={})",
-                                pause_start.elapsed().as_secs_f64() * 1000.0,
-                                spotify_dur.as_secs_f64() * 1000.0,
-                                spotify_was_paused,
-                                mpv_dur.as_secs_f64() * 1000.0,
-                                mpv_was_paused
-                            );
-
-                            // --- Confirmation beep ---
-                            // Only beep when nothing was actually paused: if
-                            // media was playing, the pause itself is obvious
-                            // feedback, so a beep would just be redundant noise.
-                            if !spotify_was_paused && !mpv_was_paused {
-                                crate::beep::play_confirmation();
-                            }
+                        // --- Pause Spotify + mpv concurrently ---
+                        // Each pause runs on its own thread so total latency
+                        // is max(spotify, mpv) instead of the sum. Both
+                        // controllers are cheap to clone (the Spotify one
+                        // caches its D-Bus connection internally).
+                        let pause_start = Instant::now();
+                        let spotify = spotify_controller.clone();
+                        let spotify_handle = thread::spawn(move || {
+                            let t = Instant::now();
+                            let paused = spotify.pause_for_wakeword();
+                            (paused, t.elapsed())
+                        });
+                        let mpv = mpv_controller.clone();
+                        let mpv_handle = thread::spawn(move || {
+                            let t = Instant::now();
+                            let paused = mpv.pause_for_wakeword();
+                            (paused, t.elapsed())
+                        });
+
+                        let (spotify_was_paused, spotify_dur) =
+                            spotify_handle.join().unwrap_or((false, Duration::ZERO));
+                        let (mpv_was_paused, mpv_dur) =
+                            mpv_handle.join().unwrap_or((false, Duration::ZERO));
 
-                            let wakeword_event = WakewordEvent {
-                                model: model_name,
-                                confidence,
-                                timestamp: ConsumerMessage::current_timestamp(),
-                                spotify_was_paused,
-                                mpv_was_paused,
-                            };
+                        log::info!(
+                            "⏱️ [Detection] media pause done in {:.1}ms (spotify {:.1}ms was_paused={}, mpv {:.1}ms was_paused={})",
+                            pause_start.elapsed().as_secs_f64() * 1000.0,
+                            spotify_dur.as_secs_f64() * 1000.0,
+                            spotify_was_paused,
+                            mpv_dur.as_secs_f64() * 1000.0,
+                            mpv_was_paused
+                        );
 
-                            return Ok((Some((wakeword_event, now)), max_conf));
+                        // --- Confirmation beep ---
+                        // Only beep when nothing was actually paused: if
+                        // media was playing, the pause itself is obvious
+                        // feedback, so a beep would just be redundant noise.
+                        #[cfg(feature = "playback")]
+                        if !spotify_was_paused && !mpv_was_paused {
+                            crate::beep::play_confirmation();
                         }
+
+                        let scores = models[strongest]
+                            .prediction_buffer(&fire.model)
+                            .map(|buffer| buffer.iter().copied().collect())
+                            .unwrap_or_default();
+
+                        let wakeword_event = WakewordEvent {
+                            model: fire.model,
+                            confidence: fire.confidence,
+                            threshold: fire.threshold,
+                            timestamp: ConsumerMessage::current_timestamp(),
+                            sample_index,
+                            scores,
+                            channel: channels[strongest],
+                            spotify_was_paused,
+                            mpv_was_paused,
+                        };
+
+                        return Ok((Some(wakeword_event), max_conf));
                     }
                 }
                 Err(e) => {
@@ -608,34 +1193,80 @@ impl ConsumerServer {
         Ok((None, max_conf))
     }
 
+    /// Score each channel's samples with its own model. The first channel
+    /// runs on the detection thread and the others on scoped threads, so the
+    /// hop costs roughly one channel's inference on a multi-core device.
+    fn predict_channels(
+        models: &mut [WakewordModel],
+        channel_samples: &[&[i16]],
+    ) -> Result<Vec<HashMap<String, f32>>, OpenWakeWordError> {
+        let Some((first, others)) = models.split_first_mut() else {
+            return Ok(Vec::new());
+        };
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = others
+                .iter_mut()
+                .zip(&channel_samples[1..])
+                .map(|(model, samples)| {
+                    scope.spawn(move || {
+                        Self::unpin_scoring_thread();
+                        model.predict(samples, None, 1.0)
+                    })
+                })
+                .collect();
+
+            let mut per_channel = vec![first.predict(channel_samples[0], None, 1.0)?];
+            for handle in handles {
+                per_channel.push(handle.join().expect("wake word scoring thread panicked")?);
+            }
+            Ok(per_channel)
+        })
+    }
+
+    /// Scoring threads inherit the detection thread's pinning to core 1; let
+    /// them use every core except core 0, which belongs to playback
+    fn unpin_scoring_thread() {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            let cores = libc::sysconf(libc::_SC_NPROCESSORS_ONLN).max(1) as usize;
+            let mut cpuset: libc::cpu_set_t = std::mem::zeroed();
+            for core in 1..cores.max(2) {
+                libc::CPU_SET(core, &mut cpuset);
+            }
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpuset);
+        }
+    }
+
     /// Handle a single consumer connection
-    fn handle_consumer(
-        &self,
-        stream: TcpStream,
-        addr: String,
-        detection_receiver: &Receiver<AudioDetectionPair>,
-    ) {
-        // Mark consumer as connected
-        self.consumer_connected.store(true, Ordering::SeqCst);
+    fn handle_consumer(&self, stream: TcpStream, addr: String) {
+        // Subscribe before spawning so the next accept sees this consumer
+        let subscription = self.subscribers.subscribe(&addr);
+        let subscriber_id = subscription.id;
+        log::info!(
+            "👥 Consumer {} subscribed ({} connected)",
+            addr,
+            self.subscribers.len()
+        );
 
         // Spawn thread to handle this consumer
         let should_stop = Arc::clone(&self.should_stop);
-        let consumer_connected = Arc::clone(&self.consumer_connected);
-
-        // Clone the detection receiver for the consumer thread
-        let detection_receiver_clone = detection_receiver.clone();
+        let subscribers = self.subscribers.clone();
+        let wakeword = self.wakeword.clone();
+        let config = self.config.clone();
 
         thread::spawn(move || {
             let result = Self::consumer_thread(
                 stream,
                 addr.clone(),
                 should_stop.clone(),
-                consumer_connected.clone(),
-                detection_receiver_clone,
+                subscription,
+                wakeword,
+                config,
             );
 
-            // Always mark consumer as disconnected when thread exits
-            consumer_connected.store(false, Ordering::SeqCst);
+            // Always unsubscribe when thread exits
+            subscribers.unsubscribe(subscriber_id);
 
             match result {
                 Ok(()) => {
@@ -658,10 +1289,41 @@ impl ConsumerServer {
         stream: TcpStream,
         addr: String,
         should_stop: Arc<AtomicBool>,
-        _consumer_connected: Arc<AtomicBool>,
-        detection_receiver: Receiver<AudioDetectionPair>,
+        subscription: Subscription,
+        wakeword: WakewordState,
+        config: ConsumerServerConfig,
     ) -> Result<(), ConsumerServerError> {
+        stream.set_nonblocking(false)?;
+        let control_stream = stream.try_clone()?;
+        let shutdown_stream = stream.try_clone()?;
+        let hello = hello_pending(&stream, ConsumerMessageType::Hello as u8, HANDSHAKE_TIMEOUT)?;
         let mut connection = ConsumerConnection::new(stream);
+        Self::negotiate_session(&mut connection, hello, &addr, &config)?;
+        // Per-window VAD probabilities only go to consumers that asked for them
+        let send_vad = connection.session().supports(CAP_VAD_PROBABILITIES);
+        let codec = connection.session().audio_codec();
+        let pairs = if codec == AudioCodec::Raw {
+            subscription.receiver.clone()
+        } else {
+            Self::start_encoder_thread(subscription.receiver.clone(), codec, addr.clone())
+        };
+
+        // Control requests are read on their own thread so a slow model reload
+        // never stalls the audio stream; replies are written from this thread
+        // between audio messages so they can't interleave with them.
+        let (reply_tx, reply_rx) = crossbeam::channel::unbounded();
+        let control_addr = addr.clone();
+        let topics = Arc::clone(&subscription.topics);
+        thread::spawn(move || {
+            Self::control_thread(
+                control_stream,
+                control_addr,
+                wakeword,
+                config,
+                topics,
+                reply_tx,
+            )
+        });
 
         // No subscription needed - client can start receiving immediately
         log::info!("✅ Consumer {} connected successfully", addr);
@@ -674,39 +1336,109 @@ impl ConsumerServer {
         let mut received_pairs = 0u64;
         let mut sent_audio = 0u64;
         let mut sent_wakewords = 0u64;
-        let dropped_by_consumer = 0u64;
         let start_time = Instant::now();
 
         while !should_stop.load(Ordering::SeqCst) {
+            if let Ok(reply) = reply_rx.try_recv() {
+                if let Err(e) = connection.write_message(&reply) {
+                    log::error!("❌ Failed to send reply to consumer {}: {}", addr, e);
+                    break;
+                }
+            }
+            if let Ok(notice) = subscription.notices.try_recv() {
+                if let Err(e) = connection.write_message(&notice) {
+                    log::error!("❌ Failed to send notice to consumer {}: {}", addr, e);
+                    break;
+                }
+            }
+
             // Receive audio-detection pairs from detection thread
-            match detection_receiver.recv_timeout(Duration::from_millis(100)) {
+            match pairs.recv_timeout(Duration::from_millis(100)) {
                 Ok(pair) => {
                     received_pairs += 1;
+                    let topics = subscription.topics.load(Ordering::Relaxed);
 
                     // Send audio chunk to consumer
-                    let audio_msg = ConsumerMessage::Audio {
-                        data: pair.audio_data,
-                        speech_detected: pair.speech_detected,
-                        timestamp: pair.timestamp,
-                    };
+                    if topics & TOPIC_AUDIO != 0 {
+                        let vad = send_vad.then(|| {
+                            pair.vad_windows
+                                .iter()
+                                .map(|w| VadProbability {
+                                    sample_index: w.sample_index,
+                                    probability: w.probability,
+                                })
+                                .collect()
+                        });
+                        let audio_msg = ConsumerMessage::Audio {
+                            data: pair.audio_data,
+                            speech_detected: pair.speech_detected,
+                            timestamp: pair.timestamp,
+                            vad,
+                        };
 
-                    match connection.write_message(&audio_msg) {
-                        Ok(()) => {
-                            sent_audio += 1;
+                        match connection.write_message(&audio_msg) {
+                            Ok(()) => {
+                                sent_audio += 1;
+                            }
+                            Err(e) => {
+                                log::error!("❌ Failed to send audio to consumer {}: {}", addr, e);
+                                break;
+                            }
                         }
-                        Err(e) => {
-                            log::error!("❌ Failed to send audio to consumer {}: {}", addr, e);
+                    }
+
+                    // Send speech boundaries completed by this chunk
+                    if topics & TOPIC_SPEECH != 0 {
+                        let at = |sample_index| {
+                            timestamp_of(pair.timestamp, pair.sample_index, sample_index)
+                        };
+                        let mut speech_failed = false;
+                        for event in &pair.speech_events {
+                            let speech_msg = match *event {
+                                SpeechEvent::Started { sample_index } => {
+                                    ConsumerMessage::SpeechStarted {
+                                        timestamp: at(sample_index),
+                                        sample_index,
+                                    }
+                                }
+                                SpeechEvent::Stopped { sample_index } => {
+                                    ConsumerMessage::SpeechStopped {
+                                        timestamp: at(sample_index),
+                                        sample_index,
+                                    }
+                                }
+                            };
+                            if let Err(e) = connection.write_message(&speech_msg) {
+                                log::error!(
+                                    "❌ Failed to send speech event to consumer {}: {}",
+                                    addr,
+                                    e
+                                );
+                                speech_failed = true;
+                                break;
+                            }
+                        }
+                        if speech_failed {
                             break;
                         }
                     }
 
                     // Send wakeword event if present
-                    if let Some(wakeword_event) = pair.wakeword_event {
+                    if let Some(wakeword_event) =
+                        pair.wakeword_event.filter(|_| topics & TOPIC_WAKEWORD != 0)
+                    {
                         let wakeword_msg = ConsumerMessage::WakewordDetected {
                             model: wakeword_event.model.clone(),
                             timestamp: wakeword_event.timestamp,
                             spotify_was_paused: wakeword_event.spotify_was_paused,
                             mpv_was_paused: wakeword_event.mpv_was_paused,
+                            details: Some(WakewordDetails {
+                                confidence: wakeword_event.confidence,
+                                threshold: wakeword_event.threshold,
+                                sample_index: wakeword_event.sample_index,
+                                scores: wakeword_event.scores,
+                                channel: Some(wakeword_event.channel as u16),
+                            }),
                         };
 
                         match connection.write_message(&wakeword_msg) {
@@ -728,10 +1460,66 @@ impl ConsumerServer {
                                 break;
                             }
                         }
+
+                        // Follow the event with the pre-roll window, oldest first
+                        let preroll = if connection.session().supports(CAP_PREROLL) {
+                            pair.preroll
+                        } else {
+                            Vec::new()
+                        };
+                        let count = preroll.len() as u16;
+                        let mut preroll_failed = false;
+                        for (index, chunk) in preroll.into_iter().enumerate() {
+                            let preroll_msg = ConsumerMessage::PrerollAudio {
+                                data: chunk.audio_data,
+                                timestamp: chunk.timestamp,
+                                index: index as u16,
+                                count,
+                            };
+                            if let Err(e) = connection.write_message(&preroll_msg) {
+                                log::error!(
+                                    "❌ Failed to send pre-roll to consumer {}: {}",
+                                    addr,
+                                    e
+                                );
+                                preroll_failed = true;
+                                break;
+                            }
+                        }
+                        if preroll_failed {
+                            break;
+                        }
+                        if count > 0 {
+                            log::info!("🎞️ [{}] Sent {} pre-roll chunks", addr, count);
+                        }
+                    }
+
+                    // Send the end of the post-wakeword utterance if present
+                    if let Some(utterance) =
+                        pair.utterance.filter(|_| topics & TOPIC_UTTERANCE != 0)
+                    {
+                        let utterance_msg = ConsumerMessage::UtteranceComplete {
+                            timestamp: timestamp_of(
+                                pair.timestamp,
+                                pair.sample_index,
+                                utterance.end_sample,
+                            ),
+                            start_sample: utterance.start_sample,
+                            end_sample: utterance.end_sample,
+                            reason: utterance.reason,
+                        };
+                        if let Err(e) = connection.write_message(&utterance_msg) {
+                            log::error!(
+                                "❌ Failed to send utterance end to consumer {}: {}",
+                                addr,
+                                e
+                            );
+                            break;
+                        }
                     }
 
-                    // Log consumer performance stats every 100 audio chunks
-                    if sent_audio % 100 == 0 {
+                    // Log consumer performance stats every 100 received pairs
+                    if received_pairs.is_multiple_of(100) {
                         let elapsed = start_time.elapsed();
                         log::debug!(
                             "📊 [{}] Consumer stats: received={} sent_audio={} sent_wakewords={} dropped={} in {:.1}s",
@@ -739,7 +1527,7 @@ impl ConsumerServer {
                             received_pairs,
                             sent_audio,
                             sent_wakewords,
-                            dropped_by_consumer,
+                            subscription.dropped.load(Ordering::Relaxed),
                             elapsed.as_secs_f64()
                         );
                     }
@@ -758,12 +1546,257 @@ impl ConsumerServer {
             }
         }
 
+        // Unblock the control thread's read
+        let _ = shutdown_stream.shutdown(std::net::Shutdown::Both);
+
         log::info!("🛑 Consumer {} disconnected", addr);
         Ok(())
     }
 
+    /// Encode the audio and pre-roll of every pair for a consumer that
+    /// negotiated `codec`, off the detection and consumer threads. Pairs come
+    /// out in order; the thread ends when either side disconnects.
+    fn start_encoder_thread(
+        receiver: Receiver<AudioDetectionPair>,
+        codec: AudioCodec,
+        addr: String,
+    ) -> Receiver<AudioDetectionPair> {
+        // A lagging consumer backs up into its subscription queue, where
+        // drops are counted, rather than here
+        let (sender, encoded) = crossbeam::channel::bounded(1);
+        thread::spawn(move || {
+            let mut encoder = AudioEncoder::new(codec);
+            let mut raw_bytes = 0u64;
+            let mut encoded_bytes = 0u64;
+            for mut pair in receiver {
+                raw_bytes += pair.audio_data.len() as u64;
+                pair.audio_data = encoder.encode(&pair.audio_data);
+                encoded_bytes += pair.audio_data.len() as u64;
+
+                // Pre-roll chunks are contiguous with each other, not with the stream
+                let mut preroll_encoder = AudioEncoder::new(codec);
+                for chunk in &mut pair.preroll {
+                    chunk.audio_data = preroll_encoder.encode(&chunk.audio_data);
+                }
+
+                if sender.send(pair).is_err() {
+                    break;
+                }
+            }
+            log::debug!(
+                "🗜️ [{}] {} encoder stopped: {} -> {} bytes",
+                addr,
+                codec,
+                raw_bytes,
+                encoded_bytes
+            );
+        });
+        encoded
+    }
+
+    /// Answer the consumer's `Hello` if it sent one, otherwise fall back to
+    /// the version 1 protocol (no pre-roll, no wakeword details)
+    fn negotiate_session(
+        connection: &mut ConsumerConnection<TcpStream>,
+        hello: bool,
+        addr: &str,
+        config: &ConsumerServerConfig,
+    ) -> Result<(), ConsumerServerError> {
+        if !hello {
+            log::info!("🤝 [{}] No handshake, using protocol v1", addr);
+            connection.set_session(Session::legacy());
+            return Ok(());
+        }
+
+        let mut offered = CAP_WAKEWORD_DETAILS
+            | CAP_VAD_PROBABILITIES
+            | CAP_AUDIO_ULAW
+            | CAP_AUDIO_ADPCM
+            | CAP_CAPTURE_STATUS;
+        if config.preroll_ms > 0 {
+            offered |= CAP_PREROLL;
+        }
+        match connection.read_message()? {
+            ConsumerMessage::Hello {
+                version,
+                capabilities,
+            } => {
+                let session = connection.accept_hello(version, capabilities, offered)?;
+                log::info!(
+                    "🤝 [{}] Negotiated protocol v{} (client v{}), capabilities {:#x}, {} audio",
+                    addr,
+                    session.version,
+                    version,
+                    session.capabilities,
+                    session.audio_codec()
+                );
+                Ok(())
+            }
+            other => Err(ProtocolError::UnexpectedHandshakeMessage(format!("{:?}", other)).into()),
+        }
+    }
+
+    /// Read control requests from a consumer until it disconnects
+    fn control_thread(
+        stream: TcpStream,
+        addr: String,
+        wakeword: WakewordState,
+        config: ConsumerServerConfig,
+        topics: Arc<AtomicU32>,
+        reply_tx: Sender<ConsumerMessage>,
+    ) {
+        let mut connection = ConsumerConnection::new(stream);
+        loop {
+            match connection.read_message() {
+                Ok(ConsumerMessage::ReloadWakeword { models, detection }) => {
+                    log::info!("🔄 [{}] Wakeword reload requested", addr);
+                    let reply = match wakeword.reload(&config, &models, &detection) {
+                        Ok(summary) => ConsumerMessage::WakewordReloaded {
+                            success: true,
+                            message: summary,
+                        },
+                        Err(e) => {
+                            log::error!("❌ [{}] Wakeword reload failed: {}", addr, e);
+                            ConsumerMessage::WakewordReloaded {
+                                success: false,
+                                message: e.to_string(),
+                            }
+                        }
+                    };
+                    if reply_tx.send(reply).is_err() {
+                        break;
+                    }
+                }
+                Ok(ConsumerMessage::Subscribe { topics: requested }) => {
+                    log::info!(
+                        "📬 [{}] Subscribed to audio={} speech={} wakeword={} utterance={}",
+                        addr,
+                        requested & TOPIC_AUDIO != 0,
+                        requested & TOPIC_SPEECH != 0,
+                        requested & TOPIC_WAKEWORD != 0,
+                        requested & TOPIC_UTTERANCE != 0
+                    );
+                    topics.store(requested, Ordering::Relaxed);
+                }
+                Ok(other) => {
+                    log::warn!(
+                        "⚠️ [{}] Ignoring unexpected message from consumer: {:?}",
+                        addr,
+                        other
+                    );
+                }
+                Err(ProtocolError::Io(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    break;
+                }
+                Err(e) => {
+                    log::debug!("🔌 [{}] Control channel closed: {}", addr, e);
+                    break;
+                }
+            }
+        }
+    }
+
     /// Stop the server
     pub fn stop(&self) {
         self.should_stop.store(true, Ordering::SeqCst);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(timestamp: u64) -> AudioDetectionPair {
+        AudioDetectionPair {
+            audio_data: vec![0; 4],
+            speech_detected: false,
+            vad_windows: Vec::new(),
+            speech_events: Vec::new(),
+            wakeword_event: None,
+            utterance: None,
+            timestamp,
+            sample_index: timestamp * 16,
+            preroll: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_broadcast_fans_out_with_per_subscriber_backpressure() {
+        let subscribers = Subscribers::default();
+        let fast = subscribers.subscribe("fast");
+        let slow = subscribers.subscribe("slow");
+
+        for timestamp in 0..SUBSCRIBER_QUEUE_CAPACITY as u64 + 5 {
+            subscribers.broadcast(pair(timestamp));
+            // Only the fast consumer keeps up
+            assert_eq!(fast.receiver.try_recv().unwrap().timestamp, timestamp);
+        }
+
+        assert_eq!(fast.dropped.load(Ordering::Relaxed), 0);
+        assert_eq!(slow.dropped.load(Ordering::Relaxed), 5);
+        assert_eq!(slow.receiver.len(), SUBSCRIBER_QUEUE_CAPACITY);
+        assert_eq!(slow.receiver.try_recv().unwrap().timestamp, 0);
+    }
+
+    #[test]
+    fn test_disconnected_subscribers_are_removed() {
+        let subscribers = Subscribers::default();
+        let kept = subscribers.subscribe("kept");
+        let gone = subscribers.subscribe("gone");
+        assert_eq!(subscribers.len(), 2);
+
+        drop(gone);
+        subscribers.broadcast(pair(0));
+        assert_eq!(subscribers.len(), 1);
+
+        subscribers.unsubscribe(kept.id);
+        assert_eq!(subscribers.len(), 0);
+    }
+
+    #[test]
+    fn test_events_only_subscribers_skip_plain_audio() {
+        let subscribers = Subscribers::default();
+        let events = subscribers.subscribe("events");
+        events
+            .topics
+            .store(TOPIC_SPEECH | TOPIC_WAKEWORD, Ordering::Relaxed);
+
+        subscribers.broadcast(pair(0));
+        assert!(events.receiver.is_empty());
+
+        let mut edge = pair(1);
+        edge.speech_events = vec![SpeechEvent::Started { sample_index: 16 }];
+        subscribers.broadcast(edge);
+        assert_eq!(
+            events.receiver.try_recv().unwrap().speech_events,
+            vec![SpeechEvent::Started { sample_index: 16 }]
+        );
+    }
+
+    #[test]
+    fn test_notices_reach_every_subscriber() {
+        let subscribers = Subscribers::default();
+        let audio = subscribers.subscribe("audio");
+        let events = subscribers.subscribe("events");
+        events.topics.store(TOPIC_WAKEWORD, Ordering::Relaxed);
+
+        subscribers.notify(ConsumerMessage::Error {
+            message: "Audio capture lost".to_string(),
+        });
+        for subscription in [&audio, &events] {
+            assert!(matches!(
+                subscription.notices.try_recv(),
+                Ok(ConsumerMessage::Error { .. })
+            ));
+            assert!(subscription.receiver.is_empty());
+        }
+
+        // A consumer that stopped reading only loses notices
+        for _ in 0..SUBSCRIBER_QUEUE_CAPACITY + 1 {
+            subscribers.notify(ConsumerMessage::Error {
+                message: "Audio capture lost".to_string(),
+            });
+        }
+        assert_eq!(audio.notices.len(), SUBSCRIBER_QUEUE_CAPACITY);
+    }
+}
@@ -1,17 +1,26 @@
+pub mod audio_codec;
 pub mod audio_sink;
 pub mod audio_source;
+#[cfg(feature = "playback")]
 pub mod beep;
 pub mod consumer_server;
 pub mod producer_server;
 pub mod protocol;
+pub mod resample;
 pub mod mpv_controller;
 pub mod spotify_controller;
 pub mod types;
 
 // Wakeword detection modules
 pub mod wakeword_error;
+pub mod wakeword_gate;
+pub mod wakeword_inference_gate;
+#[cfg(feature = "wakeword")]
+pub mod wakeword_interpreter;
+#[cfg(feature = "wakeword")]
 pub mod wakeword_model;
 pub mod wakeword_models;
+#[cfg(feature = "wakeword")]
 pub mod wakeword_utils;
 pub mod wakeword_vad;
 
@@ -2,10 +2,14 @@ use audio::audio_sink::AudioSinkConfig;
 use audio::audio_source::AudioCaptureConfig;
 use audio::consumer_server::{ConsumerServer, ConsumerServerConfig};
 use audio::producer_server::{ProducerServer, ProducerServerConfig};
+use audio::resample::ResampleQuality;
+use audio::wakeword_gate::{ChannelPolicy, DetectionOverride, DetectionSettings};
+use audio::wakeword_inference_gate::InferenceGateConfig;
 // Import wakeword configuration
-use audio::wakeword_vad::VadConfig;
+use audio::wakeword_vad::{EndpointConfig, VadBackend, VadConfig};
 use clap::Parser;
 use log::{error, info};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -17,9 +21,16 @@ use std::thread;
 Binary Audio Service providing two TCP interfaces:
 
 CONSUMER INTERFACE (Port 8080):
-  - Single consumer can subscribe for audio stream + events
+  - Up to --max-consumers consumers (default 8) each receive audio stream + events
   - Receives 16kHz s16le mono audio chunks
   - Receives events: SpeechStarted, SpeechStopped, WakewordDetected
+  - With --preroll-ms, each WakewordDetected is followed by a PrerollAudio burst
+  - Can send ReloadWakeword to swap wake word models/thresholds without a restart
+  - Can send Subscribe to pick audio, speech edges and/or wake word events
+  - SpeechStarted/SpeechStopped follow --vad-onset-ms and --vad-hangover-ms
+    using the --vad-backend speech detector
+  - After each wake word, UtteranceComplete marks where the spoken command ended
+  - CaptureStatus reports when the audio device is lost and when capture resumes
 
 PRODUCER INTERFACE (Port 8081):
   - Single producer can send audio for playback
@@ -39,8 +50,33 @@ EXAMPLES:
   # Use specific audio devices
   audio_service --input-device \"ReSpeaker 4 Mic Array\" --output-device \"Built-in Audio\"
 
+  # Capture straight from a 48kHz USB mic, resampled in the service
+  audio_service --input-device \"Jabra SPEAK 510 USB\" --resample-quality high
+
   # Boost TTS volume by 30 percentage points
   audio_service --mixer-name \"PCM\" --tts-volume-boost 30
+
+  # Listen for two wake words, with models from a custom directory
+  audio_service --model-dir /opt/models --wakeword-model hey_mycroft --wakeword-model alexa
+
+  # Stricter threshold and shorter lockout for one wake word
+  audio_service --wakeword-detection hey_mycroft:threshold=0.6,refractory_ms=1500
+
+  # Send the 1.5s of audio leading up to each wake word for STT
+  audio_service --preroll-ms 1500
+
+  # Allow the agent, a recorder and a dashboard to connect at once
+  audio_service --consumer-bind 0.0.0.0:8080 --max-consumers 3
+
+  # Save CPU on the Pi by skipping wake word inference in a silent room
+  audio_service --inference-gate --inference-gate-dbfs -60
+
+  # Stream mic 0 of an array but listen for the wake word on mics 0-3
+  audio_service --input-channel 0 --wakeword-channel 0 --wakeword-channel 1 \\
+    --wakeword-channel 2 --wakeword-channel 3 --channel-policy any
+
+  # Start faster by reusing the wake word feature warmup across restarts
+  audio_service --feature-snapshot /var/cache/audio/features.bin
 ")]
 struct Args {
     /// Consumer server bind address (for audio streaming)
@@ -67,6 +103,22 @@ struct Args {
     #[arg(long, default_value = "0")]
     input_channel: u32,
 
+    /// Input channel to score for wake words, each with its own copy of the
+    /// models. Repeatable. Defaults to the --input-channel only
+    #[arg(long = "wakeword-channel", value_name = "CHANNEL")]
+    wakeword_channels: Vec<u32>,
+
+    /// How the --wakeword-channel scores combine: `any` channel, `all`
+    /// channels or a `majority` of them must cross the threshold
+    #[arg(long, default_value_t = ChannelPolicy::default())]
+    channel_policy: ChannelPolicy,
+
+    /// Resampler for input devices that don't run at 16kHz and output devices
+    /// that don't run at 48kHz: `fast` (linear), `balanced` or `high`
+    /// (windowed sinc)
+    #[arg(long, default_value_t = ResampleQuality::default())]
+    resample_quality: ResampleQuality,
+
     /// Software capture gain in dB applied to mic input (0 = unchanged).
     /// Raises low-output USB mics (e.g. the Jabra speakerphone) into the level
     /// openWakeWord expects. Leave at 0 for the ReSpeaker, which is already hot.
@@ -92,6 +144,95 @@ struct Args {
     /// no-op and detection is unaffected.
     #[arg(long, default_value = "127.0.0.1:3001")]
     spotify_endpoint: String,
+
+    /// Wake word model to load: the name of a manifest in --model-dir (e.g.
+    /// hey_mycroft), or a path to a manifest or .tflite file. Repeatable.
+    #[arg(
+        long = "wakeword-model",
+        value_name = "NAME|PATH",
+        default_value = "hey_mycroft"
+    )]
+    wakeword_models: Vec<String>,
+
+    /// Directory containing the wake word models and the melspectrogram and
+    /// embedding feature models
+    #[arg(long, default_value = "models")]
+    model_dir: String,
+
+    /// Cache the feature extractor's warmed-up state in this file so startup
+    /// and model reloads skip the warmup inference. Rewritten when the
+    /// feature models change, and with the room tone after the first 10s
+    /// without speech
+    #[arg(long)]
+    feature_snapshot: Option<String>,
+
+    /// Wake word detection threshold (0.0-1.0) for every model without an
+    /// override. Defaults to each model's manifest threshold, or 0.5
+    #[arg(long)]
+    detection_threshold: Option<f32>,
+
+    /// Minimum time in ms between two detections of the same wake word
+    #[arg(long, default_value = "3000")]
+    debounce_ms: u64,
+
+    /// Time in ms after a detection during which no wake word may fire
+    #[arg(long, default_value = "3000")]
+    refractory_ms: u64,
+
+    /// Per-model detection override, e.g. `hey_mycroft:threshold=0.6,debounce_ms=2000`.
+    /// Keys: threshold, debounce_ms, refractory_ms. Repeatable.
+    #[arg(long = "wakeword-detection", value_name = "MODEL:KEY=VALUE,...")]
+    wakeword_detection: Vec<String>,
+
+    /// Milliseconds of audio before and including the wake word to send to the
+    /// consumer as PrerollAudio after each detection (0 = disabled)
+    #[arg(long, default_value = "0")]
+    preroll_ms: u64,
+
+    /// Maximum number of consumers connected at the same time. Each gets its
+    /// own copy of the audio and event stream.
+    #[arg(long, default_value = "8")]
+    max_consumers: usize,
+
+    /// Milliseconds of continuous speech before SpeechStarted is sent
+    #[arg(long, default_value = "96")]
+    vad_onset_ms: u64,
+
+    /// Milliseconds of continuous silence before SpeechStopped is sent
+    #[arg(long, default_value = "800")]
+    vad_hangover_ms: u64,
+
+    /// Speech detector: `silero` (neural, via ONNX Runtime) or `energy`
+    /// (energy and zero-crossing rate, for low-power devices). Builds
+    /// without the `vad` feature only have `energy`
+    #[arg(long, default_value_t = VadBackend::default())]
+    vad_backend: VadBackend,
+
+    /// Milliseconds of silence after speech that end an utterance following
+    /// a wake word
+    #[arg(long, default_value = "1000")]
+    endpoint_silence_ms: u64,
+
+    /// Maximum utterance length in milliseconds, measured from speech onset
+    #[arg(long, default_value = "15000")]
+    endpoint_max_ms: u64,
+
+    /// Milliseconds after a wake word to wait for speech before giving up
+    #[arg(long, default_value = "5000")]
+    endpoint_no_speech_ms: u64,
+
+    /// Skip embedding and wake word inference during sustained silence. The
+    /// melspectrogram keeps running so detection is unchanged once sound returns.
+    #[arg(long)]
+    inference_gate: bool,
+
+    /// Chunks at or above this RMS level (dBFS) reopen the inference gate
+    #[arg(long, default_value = "-55", allow_negative_numbers = true)]
+    inference_gate_dbfs: f32,
+
+    /// Milliseconds of continuous quiet before the inference gate closes
+    #[arg(long, default_value = "2000")]
+    inference_gate_hold_ms: u64,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -108,16 +249,56 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("🎯 Consumer server: {}", args.consumer_bind);
     info!("🔊 Producer server: {}", args.producer_bind);
 
+    let detection = DetectionSettings {
+        threshold: args
+            .detection_threshold
+            .unwrap_or(DetectionSettings::default().threshold),
+        debounce_ms: args.debounce_ms,
+        refractory_ms: args.refractory_ms,
+    };
+    let model_detection = args
+        .wakeword_detection
+        .iter()
+        .map(|spec| DetectionOverride::parse(spec))
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| format!("--wakeword-detection: {}", e))?;
+
     let consumer_config = ConsumerServerConfig {
         bind_address: args.consumer_bind,
         audio_capture_config: AudioCaptureConfig {
             device_id: args.input_device.clone(),
             channel: args.input_channel,
             gain: 10f32.powf(args.capture_gain / 20.0),
+            all_channels: false,
+            required_channels: Vec::new(),
+            resample_quality: args.resample_quality,
+        },
+        wakeword_models: args.wakeword_models.clone(),
+        wakeword_channels: args.wakeword_channels.clone(),
+        channel_policy: args.channel_policy,
+        wakeword_model_dir: args.model_dir.clone(),
+        feature_snapshot: args.feature_snapshot.clone(),
+        detection,
+        // Manifest thresholds apply unless a global threshold was given explicitly
+        manifest_thresholds: args.detection_threshold.is_none(),
+        model_detection,
+        preroll_ms: args.preroll_ms,
+        max_consumers: args.max_consumers,
+        vad_config: VadConfig {
+            backend: args.vad_backend,
+            onset_ms: args.vad_onset_ms,
+            hangover_ms: args.vad_hangover_ms,
+            ..VadConfig::default()
         },
-        wakeword_models: vec!["hey_mycroft".to_string()],
-        detection_threshold: 0.5,
-        vad_config: VadConfig::default(),
+        endpoint: EndpointConfig {
+            min_silence_ms: args.endpoint_silence_ms,
+            max_utterance_ms: args.endpoint_max_ms,
+            no_speech_timeout_ms: args.endpoint_no_speech_ms,
+        },
+        inference_gate: args.inference_gate.then_some(InferenceGateConfig {
+            open_dbfs: args.inference_gate_dbfs,
+            hold_ms: args.inference_gate_hold_ms,
+        }),
         led_endpoint: args.led_endpoint.clone(),
         spotify_endpoint: args.spotify_endpoint.clone(),
     };
@@ -126,6 +307,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         bind_address: args.producer_bind,
         audio_sink_config: AudioSinkConfig {
             device_name: args.output_device.clone(),
+            resample_quality: args.resample_quality,
         },
         mixer_name: args.mixer_name.clone(),
         tts_volume_boost: args.tts_volume_boost,
@@ -145,6 +327,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     consumer_server.set_barge_in_sender(barge_in_tx);
     producer_server.set_barge_in_receiver(barge_in_rx);
 
+    // Load wakeword models up front: a missing or broken model is fatal, and
+    // should be reported now rather than after the detection thread starts
+    if let Err(e) = consumer_server.initialize_wakeword_model() {
+        error!("❌ {}", e);
+        return Err(e.into());
+    }
+
     // Pre-initialize audio sink to prevent audio loss on first connection
     if let Err(e) = producer_server.initialize_sink() {
         error!("Failed to pre-initialize audio sink: {}", e);
@@ -1,6 +1,11 @@
-use crate::audio_sink::{AudioSink, AudioSinkConfig};
-use crate::protocol::{ProducerConnection, ProducerMessage, ProtocolError};
+use crate::audio_sink::{AudioSink, AudioSinkConfig, TARGET_SAMPLE_RATE};
+use crate::protocol::{
+    hello_pending, AudioEncoding, ProducerConnection, ProducerMessage, ProducerMessageType,
+    ProtocolError, Session, CAP_STREAM_FORMAT, HANDSHAKE_TIMEOUT,
+};
+use crate::resample::{ResampleQuality, Resampler};
 use crossbeam::channel::Receiver;
+use std::collections::HashMap;
 use std::net::{TcpListener, TcpStream};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
@@ -9,6 +14,12 @@ use std::thread;
 use std::time::Duration;
 use thiserror::Error;
 
+/// Range of sample rates accepted in `StreamFormat`
+const MIN_STREAM_RATE: u32 = 8000;
+const MAX_STREAM_RATE: u32 = 192000;
+/// Most interleaved channels accepted in `StreamFormat`
+const MAX_STREAM_CHANNELS: u16 = 8;
+
 #[derive(Error, Debug)]
 pub enum ProducerServerError {
     #[error("IO error: {0}")]
@@ -205,11 +216,16 @@ impl ProducerServer {
         mixer_name: Option<String>,
         tts_volume_boost: u8,
     ) -> Result<(), ProducerServerError> {
+        stream.set_nonblocking(false)?;
+        let hello = hello_pending(&stream, ProducerMessageType::Hello as u8, HANDSHAKE_TIMEOUT)?;
         let mut connection = ProducerConnection::new(stream);
+        Self::negotiate_session(&mut connection, hello, &addr)?;
 
         // No connection confirmation needed - client can start sending immediately
         log::info!("✅ Producer {} connected successfully", addr);
 
+        let quality = sink_config.resample_quality;
+
         // Initialize audio sink if not already running
         {
             let mut sink_guard = audio_sink.lock().unwrap();
@@ -238,6 +254,9 @@ impl ProducerServer {
         let mut interrupted_stream_id: u64 = 0; // Last interrupted stream
         let mut pending_completion: Option<mpsc::Receiver<()>> = None;
         let mut saved_volume: Option<u8> = None;
+        // Streams declared with StreamFormat; others are already mono 48kHz s16le
+        let mut decoders: HashMap<u64, StreamDecoder> = HashMap::new();
+        let mut rejected_stream_id: u64 = 0; // Last stream with an unsupported format
 
         while !should_stop.load(Ordering::SeqCst) {
             // Check for barge-in signal from consumer (wakeword detected during playback)
@@ -260,7 +279,7 @@ impl ProducerServer {
                             if let Some(vol) = saved_volume.take() {
                                 if let Some(ref mixer) = mixer_name {
                                     log::info!("🔊 Restoring volume to {}% after barge-in", vol);
-                                    alsa_volume::set_volume(mixer, vol);
+                                    set_mixer_volume(mixer, vol);
                                 }
                             }
 
@@ -312,7 +331,7 @@ impl ProducerServer {
                         if let Some(vol) = saved_volume.take() {
                             if let Some(ref mixer) = mixer_name {
                                 log::info!("🔊 Restoring volume to {}% after playback", vol);
-                                alsa_volume::set_volume(mixer, vol);
+                                set_mixer_volume(mixer, vol);
                             }
                         }
 
@@ -370,6 +389,16 @@ impl ProducerServer {
                                 continue;
                             }
 
+                            // Its format was refused, so the data can't be played
+                            if stream_id == rejected_stream_id {
+                                log::debug!(
+                                    "🗑️  Dropping {} bytes from stream {} with unsupported format",
+                                    data.len(),
+                                    stream_id
+                                );
+                                continue;
+                            }
+
                             // Check if new stream is starting
                             if stream_id != current_stream_id {
                                 log::info!(
@@ -391,7 +420,7 @@ impl ProducerServer {
 
                                 // Boost volume for TTS playback
                                 if let Some(ref mixer) = mixer_name {
-                                    match alsa_volume::get_volume(mixer) {
+                                    match get_mixer_volume(mixer) {
                                         Ok(current) => {
                                             saved_volume = Some(current);
                                             let boosted = (current as u16 + tts_volume_boost as u16).min(100) as u8;
@@ -399,7 +428,7 @@ impl ProducerServer {
                                                 "🔊 TTS volume boost: {}% -> {}% (+{})",
                                                 current, boosted, tts_volume_boost
                                             );
-                                            alsa_volume::set_volume(mixer, boosted);
+                                            set_mixer_volume(mixer, boosted);
                                         }
                                         Err(e) => {
                                             log::warn!("⚠️  Could not read volume for TTS boost: {}", e);
@@ -408,8 +437,14 @@ impl ProducerServer {
                                 }
 
                                 current_stream_id = stream_id;
+                                decoders.retain(|&id, _| id >= stream_id);
                             }
 
+                            let data = match decoders.get_mut(&stream_id) {
+                                Some(decoder) => decoder.decode(&data),
+                                None => data,
+                            };
+
                             // Send audio to sink WITH stream_id - sink handles switching!
                             let sink_guard = audio_sink.lock().unwrap();
                             if let Some(sink) = sink_guard.as_ref() {
@@ -445,6 +480,19 @@ impl ProducerServer {
                                 // Start non-blocking completion wait
                                 let sink_guard = audio_sink.lock().unwrap();
                                 if let Some(sink) = sink_guard.as_ref() {
+                                    // Play what the resampler still holds back
+                                    if let Some(mut decoder) = decoders.remove(&stream_id) {
+                                        let tail = decoder.flush();
+                                        if !tail.is_empty() {
+                                            if let Err(e) = sink.write_chunk(tail, stream_id) {
+                                                log::error!(
+                                                    "❌ Failed to write audio to sink: {}",
+                                                    e
+                                                );
+                                            }
+                                        }
+                                    }
+
                                     match sink.end_stream() {
                                         Ok(completion_rx) => {
                                             log::info!(
@@ -472,7 +520,52 @@ impl ProducerServer {
                                 );
                             }
                         }
+                        ProducerMessage::StreamFormat {
+                            stream_id,
+                            sample_rate,
+                            channels,
+                            encoding,
+                        } => {
+                            if stream_id <= interrupted_stream_id {
+                                log::info!(
+                                    "🗑️  Ignoring format of old/interrupted stream {}",
+                                    stream_id
+                                );
+                                continue;
+                            }
+
+                            match StreamDecoder::new(sample_rate, channels, encoding, quality) {
+                                Ok(decoder) => {
+                                    log::info!(
+                                        "🎚️  Stream {} format: {}Hz, {} channel(s), {}",
+                                        stream_id,
+                                        sample_rate,
+                                        channels,
+                                        encoding
+                                    );
+                                    decoders.insert(stream_id, decoder);
+                                }
+                                Err(reason) => {
+                                    log::warn!(
+                                        "⚠️  Rejecting format of stream {} from producer {}: {}",
+                                        stream_id,
+                                        addr,
+                                        reason
+                                    );
+                                    decoders.remove(&stream_id);
+                                    rejected_stream_id = stream_id;
+                                    let error_msg = ProducerMessage::Error {
+                                        message: format!(
+                                            "Unsupported format for stream {}: {}",
+                                            stream_id, reason
+                                        ),
+                                    };
+                                    connection.write_message(&error_msg)?;
+                                }
+                            }
+                        }
                         ProducerMessage::Error { .. }
+                        | ProducerMessage::Hello { .. }
                         | ProducerMessage::PlaybackComplete { .. } => {
                             // These are server-to-client messages, should not be received
                             log::warn!(
@@ -502,8 +595,232 @@ impl ProducerServer {
         Ok(())
     }
 
+    /// Answer the producer's `Hello` if it sent one, otherwise fall back to
+    /// the version 1 protocol
+    fn negotiate_session(
+        connection: &mut ProducerConnection<TcpStream>,
+        hello: bool,
+        addr: &str,
+    ) -> Result<(), ProducerServerError> {
+        if !hello {
+            log::info!("🤝 [{}] No handshake, using protocol v1", addr);
+            connection.set_session(Session::legacy());
+            return Ok(());
+        }
+
+        match connection.read_message()? {
+            ProducerMessage::Hello {
+                version,
+                capabilities,
+            } => {
+                let session = connection.accept_hello(version, capabilities, CAP_STREAM_FORMAT)?;
+                log::info!(
+                    "🤝 [{}] Negotiated protocol v{} (client v{})",
+                    addr,
+                    session.version,
+                    version
+                );
+                Ok(())
+            }
+            other => Err(ProtocolError::UnexpectedHandshakeMessage(format!("{:?}", other)).into()),
+        }
+    }
+
     /// Stop the server
     pub fn stop(&self) {
         self.should_stop.store(true, Ordering::SeqCst);
     }
 }
+
+/// Converts one stream's declared `Play` format to the mono 48kHz s16le the
+/// sink expects: decodes samples, averages channels and resamples
+struct StreamDecoder {
+    encoding: AudioEncoding,
+    channels: usize,
+    resampler: Resampler,
+    /// Bytes of a frame split across `Play` messages
+    partial: Vec<u8>,
+    mono: Vec<f32>,
+    resampled: Vec<f32>,
+}
+
+impl StreamDecoder {
+    /// A decoder for the declared format, or why it can't be played
+    fn new(
+        sample_rate: u32,
+        channels: u16,
+        encoding: AudioEncoding,
+        quality: ResampleQuality,
+    ) -> Result<Self, String> {
+        if encoding == AudioEncoding::Opus {
+            return Err("opus is not supported, send s16le or f32le".to_string());
+        }
+        if !(MIN_STREAM_RATE..=MAX_STREAM_RATE).contains(&sample_rate) {
+            return Err(format!(
+                "sample rate {}Hz outside {}..={}Hz",
+                sample_rate, MIN_STREAM_RATE, MAX_STREAM_RATE
+            ));
+        }
+        if channels == 0 || channels > MAX_STREAM_CHANNELS {
+            return Err(format!(
+                "{} channels outside 1..={}",
+                channels, MAX_STREAM_CHANNELS
+            ));
+        }
+
+        Ok(Self {
+            encoding,
+            channels: channels as usize,
+            resampler: Resampler::new(sample_rate, TARGET_SAMPLE_RATE, quality),
+            partial: Vec::new(),
+            mono: Vec::new(),
+            resampled: Vec::new(),
+        })
+    }
+
+    fn frame_bytes(&self) -> usize {
+        let sample_bytes = match self.encoding {
+            AudioEncoding::F32le => 4,
+            _ => 2,
+        };
+        sample_bytes * self.channels
+    }
+
+    /// Convert the whole frames of `data`, keeping a trailing partial frame
+    /// for the next call
+    fn decode(&mut self, data: &[u8]) -> Vec<u8> {
+        self.partial.extend_from_slice(data);
+        let frame_bytes = self.frame_bytes();
+        let whole = self.partial.len() / frame_bytes * frame_bytes;
+
+        self.mono.clear();
+        for frame in self.partial[..whole].chunks_exact(frame_bytes) {
+            let sum: f32 = match self.encoding {
+                AudioEncoding::F32le => frame
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .sum(),
+                _ => frame
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                    .sum(),
+            };
+            self.mono.push(sum / self.channels as f32);
+        }
+        self.partial.drain(..whole);
+
+        self.resampled.clear();
+        self.resampler.process(&self.mono, &mut self.resampled);
+        self.to_s16le()
+    }
+
+    /// Samples the resampler still holds back at the end of the stream
+    fn flush(&mut self) -> Vec<u8> {
+        self.partial.clear();
+        self.resampled.clear();
+        self.resampler.flush(&mut self.resampled);
+        self.to_s16le()
+    }
+
+    fn to_s16le(&self) -> Vec<u8> {
+        self.resampled
+            .iter()
+            .map(|&x| {
+                (x * 32768.0)
+                    .round()
+                    .clamp(i16::MIN as f32, i16::MAX as f32) as i16
+            })
+            .flat_map(i16::to_le_bytes)
+            .collect()
+    }
+}
+
+/// Current volume of `mixer`, saved so it can be restored after the TTS boost
+#[cfg(feature = "playback")]
+fn get_mixer_volume(mixer: &str) -> Result<u8, String> {
+    alsa_volume::get_volume(mixer).map_err(|e| e.to_string())
+}
+
+/// Without the `playback` feature there is no mixer, so nothing is boosted
+#[cfg(not(feature = "playback"))]
+fn get_mixer_volume(_mixer: &str) -> Result<u8, String> {
+    Err("mixer control needs the `playback` feature".to_string())
+}
+
+#[cfg(feature = "playback")]
+fn set_mixer_volume(mixer: &str, percent: u8) {
+    alsa_volume::set_volume(mixer, percent);
+}
+
+#[cfg(not(feature = "playback"))]
+fn set_mixer_volume(_mixer: &str, _percent: u8) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s16le(samples: impl IntoIterator<Item = i16>) -> Vec<u8> {
+        samples.into_iter().flat_map(i16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_native_format_is_unchanged() {
+        let mut decoder = StreamDecoder::new(
+            TARGET_SAMPLE_RATE,
+            1,
+            AudioEncoding::S16le,
+            ResampleQuality::High,
+        )
+        .unwrap();
+        let data = s16le((0..480).map(|n| (n * 64 - 15000) as i16));
+
+        // A sample split across two messages is reassembled
+        let mut out = decoder.decode(&data[..101]);
+        out.extend(decoder.decode(&data[101..]));
+        out.extend(decoder.flush());
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_stereo_f32_is_downmixed_and_resampled() {
+        let mut decoder =
+            StreamDecoder::new(24000, 2, AudioEncoding::F32le, ResampleQuality::Balanced).unwrap();
+        let data: Vec<u8> = (0..24000)
+            .flat_map(|n| {
+                let x = 0.5 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / 24000.0).sin();
+                [x, -0.25].into_iter().flat_map(f32::to_le_bytes)
+            })
+            .collect();
+
+        let mut out = Vec::new();
+        for piece in data.chunks(1000) {
+            out.extend(decoder.decode(piece));
+        }
+        out.extend(decoder.flush());
+
+        // One second at 48kHz, averaged around the right channel's offset
+        assert_eq!(out.len(), 2 * TARGET_SAMPLE_RATE as usize);
+        let samples: Vec<i16> = out
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+        assert!((mean + 0.125 * 32768.0).abs() < 100.0, "mean {}", mean);
+        let peak = samples.iter().copied().max().unwrap();
+        assert!(
+            (peak as f64 - 0.125 * 32768.0).abs() < 400.0,
+            "peak {}",
+            peak
+        );
+    }
+
+    #[test]
+    fn test_unsupported_formats_are_rejected() {
+        let quality = ResampleQuality::default();
+        assert!(StreamDecoder::new(48000, 1, AudioEncoding::Opus, quality).is_err());
+        assert!(StreamDecoder::new(0, 1, AudioEncoding::S16le, quality).is_err());
+        assert!(StreamDecoder::new(22050, 0, AudioEncoding::S16le, quality).is_err());
+        assert!(StreamDecoder::new(22050, 9, AudioEncoding::F32le, quality).is_err());
+        assert!(StreamDecoder::new(22050, 2, AudioEncoding::S16le, quality).is_ok());
+    }
+}
@@ -1,4 +1,7 @@
+use crate::audio_codec::AudioCodec;
 use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,16 +17,162 @@ pub enum ProtocolError {
 
     #[error("Invalid message type: {0}")]
     InvalidMessageType(u8),
+
+    #[error("Invalid utterance end reason: {0}")]
+    InvalidUtteranceEndReason(u8),
+
+    #[error("Invalid audio encoding: {0}")]
+    InvalidAudioEncoding(u8),
+
+    #[error("Unsupported protocol version {peer} (supported: {min}..={max})")]
+    UnsupportedVersion { peer: u16, min: u16, max: u16 },
+
+    #[error("Handshake rejected by peer: {0}")]
+    HandshakeRejected(String),
+
+    #[error("Unexpected message during handshake: {0}")]
+    UnexpectedHandshakeMessage(String),
+}
+
+/// Protocol version spoken by this crate. Version 1 is the original protocol
+/// without a handshake; peers that never send `Hello` are treated as version 1.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// Oldest protocol version still accepted from a peer
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Capability: the peer understands `PrerollAudio` bursts after a wake word
+pub const CAP_PREROLL: u32 = 1 << 0;
+/// Capability: the peer parses the `WakewordDetails` extension of `WakewordDetected`
+pub const CAP_WAKEWORD_DETAILS: u32 = 1 << 1;
+/// Capability: the peer parses per-window VAD probabilities appended to `Audio`
+pub const CAP_VAD_PROBABILITIES: u32 = 1 << 3;
+/// Capability: the server converts `Play` audio declared with `StreamFormat`
+pub const CAP_STREAM_FORMAT: u32 = 1 << 4;
+/// Capability: `Audio` and `PrerollAudio` data is G.711 µ-law
+pub const CAP_AUDIO_ULAW: u32 = 1 << 5;
+/// Capability: `Audio` and `PrerollAudio` data is IMA ADPCM; preferred over
+/// µ-law when both are negotiated
+pub const CAP_AUDIO_ADPCM: u32 = 1 << 6;
+/// Capability: the peer understands `CaptureStatus`; others only hear about a
+/// lost capture, as an `Error`
+pub const CAP_CAPTURE_STATUS: u32 = 1 << 7;
+
+/// How long a server waits for a `Hello` before treating the peer as a
+/// version 1 client. Any first byte ends the wait at once, so only a legacy
+/// client that sends nothing (e.g. a consumer that just listens for audio)
+/// sees its first message this much later.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Protocol version and capabilities agreed with a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    pub version: u16,
+    pub capabilities: u32,
+}
+
+impl Session {
+    /// A peer that never sent `Hello`: original protocol, no optional messages
+    pub fn legacy() -> Self {
+        Self {
+            version: 1,
+            capabilities: 0,
+        }
+    }
+
+    /// Negotiate with a peer's `Hello`: the lower of the two versions and the
+    /// capabilities both sides offer. Fails if that version is too old.
+    pub fn negotiate(
+        peer_version: u16,
+        peer_capabilities: u32,
+        local_capabilities: u32,
+    ) -> Result<Self, ProtocolError> {
+        if peer_version < MIN_PROTOCOL_VERSION {
+            return Err(ProtocolError::UnsupportedVersion {
+                peer: peer_version,
+                min: MIN_PROTOCOL_VERSION,
+                max: PROTOCOL_VERSION,
+            });
+        }
+        Ok(Self {
+            version: peer_version.min(PROTOCOL_VERSION),
+            capabilities: peer_capabilities & local_capabilities,
+        })
+    }
+
+    pub fn supports(&self, capability: u32) -> bool {
+        self.capabilities & capability == capability
+    }
+
+    /// Encoding of consumer audio payloads in this session
+    pub fn audio_codec(&self) -> AudioCodec {
+        if self.supports(CAP_AUDIO_ADPCM) {
+            AudioCodec::ImaAdpcm
+        } else if self.supports(CAP_AUDIO_ULAW) {
+            AudioCodec::Ulaw
+        } else {
+            AudioCodec::Raw
+        }
+    }
+}
+
+impl Default for Session {
+    /// Everything this crate can speak; used until a handshake says otherwise
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: CAP_PREROLL | CAP_WAKEWORD_DETAILS,
+        }
+    }
+}
+
+/// Wait up to `timeout` for the peer's first byte and report whether it starts
+/// a `Hello` of type `hello_type`. The byte is only peeked, so a version 1
+/// client's first message is still read normally. Returns as soon as any byte
+/// arrives; only a silent peer waits out the full `timeout`. Restores a
+/// blocking read.
+pub fn hello_pending(
+    stream: &TcpStream,
+    hello_type: u8,
+    timeout: Duration,
+) -> Result<bool, ProtocolError> {
+    stream.set_read_timeout(Some(timeout))?;
+    let mut first = [0u8; 1];
+    let result = match stream.peek(&mut first) {
+        Ok(n) => Ok(n == 1 && first[0] == hello_type),
+        Err(ref e)
+            if e.kind() == std::io::ErrorKind::WouldBlock
+                || e.kind() == std::io::ErrorKind::TimedOut =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(e.into()),
+    };
+    stream.set_read_timeout(None)?;
+    result
 }
 
 /// Consumer message types (Port 8080)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ConsumerMessageType {
+    // Client → Audio Crate
+    ReloadWakeword = 0x01,
+    Subscribe = 0x03,
+
+    // Both directions: client offer, then server answer
+    Hello = 0x02,
+
     // Audio Crate → Client
     Error = 0x11,
     Audio = 0x12,
+    SpeechStarted = 0x13,
+    SpeechStopped = 0x14,
     WakewordDetected = 0x15,
+    WakewordReloaded = 0x16,
+    PrerollAudio = 0x17,
+    UtteranceComplete = 0x18,
+    CaptureStatus = 0x19,
 }
 
 impl TryFrom<u8> for ConsumerMessageType {
@@ -31,9 +180,18 @@ impl TryFrom<u8> for ConsumerMessageType {
 
     fn try_from(value: u8) -> Result<Self, ProtocolError> {
         match value {
+            0x01 => Ok(ConsumerMessageType::ReloadWakeword),
+            0x02 => Ok(ConsumerMessageType::Hello),
+            0x03 => Ok(ConsumerMessageType::Subscribe),
             0x11 => Ok(ConsumerMessageType::Error),
             0x12 => Ok(ConsumerMessageType::Audio),
+            0x13 => Ok(ConsumerMessageType::SpeechStarted),
+            0x14 => Ok(ConsumerMessageType::SpeechStopped),
             0x15 => Ok(ConsumerMessageType::WakewordDetected),
+            0x16 => Ok(ConsumerMessageType::WakewordReloaded),
+            0x17 => Ok(ConsumerMessageType::PrerollAudio),
+            0x18 => Ok(ConsumerMessageType::UtteranceComplete),
+            0x19 => Ok(ConsumerMessageType::CaptureStatus),
             _ => Err(ProtocolError::InvalidMessageType(value)),
         }
     }
@@ -47,6 +205,10 @@ pub enum ProducerMessageType {
     Play = 0x20,
     // Stop = 0x21,  // REMOVED: Barge-in only stops server-side
     EndOfStream = 0x22,
+    StreamFormat = 0x24,
+
+    // Both directions: client offer, then server answer
+    Hello = 0x23,
 
     // Audio Crate → Client
     Error = 0x31,
@@ -61,6 +223,8 @@ impl TryFrom<u8> for ProducerMessageType {
             0x20 => Ok(ProducerMessageType::Play),
             // 0x21 Stop removed
             0x22 => Ok(ProducerMessageType::EndOfStream),
+            0x23 => Ok(ProducerMessageType::Hello),
+            0x24 => Ok(ProducerMessageType::StreamFormat),
             0x31 => Ok(ProducerMessageType::Error),
             0x32 => Ok(ProducerMessageType::PlaybackComplete),
             _ => Err(ProtocolError::InvalidMessageType(value)),
@@ -71,21 +235,247 @@ impl TryFrom<u8> for ProducerMessageType {
 /// Consumer protocol messages
 #[derive(Debug, Clone)]
 pub enum ConsumerMessage {
+    // Client → Audio Crate
+    /// Swap in new wakeword models without restarting the service. Empty
+    /// lists keep the service's configured models / detection overrides.
+    ReloadWakeword {
+        models: Vec<String>,    // Manifest names or model paths
+        detection: Vec<String>, // "MODEL:threshold=0.6,debounce_ms=2000" overrides
+    },
+    /// Choose which messages this consumer receives; replaces the previous
+    /// choice. Until one is sent a consumer gets `DEFAULT_TOPICS`.
+    Subscribe {
+        topics: u32, // TOPIC_* flags
+    },
+
+    /// Handshake: the client's offer, answered with the negotiated session
+    Hello {
+        version: u16,
+        capabilities: u32, // CAP_* flags
+    },
+
     // Audio Crate → Client
     Error {
         message: String,
     },
     Audio {
         data: Vec<u8>,
-        speech_detected: bool, // VAD result for this chunk
-        timestamp: u64,        // When this chunk was captured (ms since epoch)
+        speech_detected: bool,            // VAD result for this chunk
+        timestamp: u64,                   // When this chunk was captured (ms since epoch)
+        vad: Option<Vec<VadProbability>>, // Per-window extension, absent from older servers
+    },
+    /// Speech lasted the VAD onset time; boundary is where it began
+    SpeechStarted {
+        timestamp: u64,    // Estimated capture time of sample_index (ms since epoch)
+        sample_index: u64, // First sample of the first speech window
+    },
+    /// Silence lasted the VAD hangover time; boundary is where it began
+    SpeechStopped {
+        timestamp: u64,    // Estimated capture time of sample_index (ms since epoch)
+        sample_index: u64, // First sample of the first silent window
     },
     WakewordDetected {
         model: String,
         timestamp: u64,
         spotify_was_paused: bool,
         mpv_was_paused: bool,
+        details: Option<WakewordDetails>, // Versioned extension, absent from older servers
+    },
+    /// Result of a ReloadWakeword request
+    WakewordReloaded {
+        success: bool,
+        message: String, // Loaded models on success, the load error otherwise
+    },
+    /// One chunk of the audio buffered before a wake word, sent as a burst
+    /// (index 0..count, oldest first) right after WakewordDetected
+    PrerollAudio {
+        data: Vec<u8>,
+        timestamp: u64, // Original capture time of this chunk (ms since epoch)
+        index: u16,
+        count: u16,
     },
+    /// The utterance after a wake word ended
+    UtteranceComplete {
+        timestamp: u64,    // Estimated capture time of end_sample (ms since epoch)
+        start_sample: u64, // First speech sample, or where listening began if none
+        end_sample: u64,   // Capture sample index where the utterance ends
+        reason: UtteranceEndReason,
+    },
+    /// The audio device was lost, or capture resumed after a loss
+    CaptureStatus {
+        lost: bool,
+        deaf_ms: u64,   // How long capture was down; 0 while lost
+        reason: String, // Why capture was lost; empty once restored
+    },
+}
+
+/// Why an utterance was considered complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum UtteranceEndReason {
+    /// The user stopped talking
+    Silence = 0,
+    /// Speech ran past the maximum utterance length
+    MaxLength = 1,
+    /// No speech followed the wake word
+    NoSpeech = 2,
+}
+
+impl TryFrom<u8> for UtteranceEndReason {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, ProtocolError> {
+        match value {
+            0 => Ok(UtteranceEndReason::Silence),
+            1 => Ok(UtteranceEndReason::MaxLength),
+            2 => Ok(UtteranceEndReason::NoSpeech),
+            _ => Err(ProtocolError::InvalidUtteranceEndReason(value)),
+        }
+    }
+}
+
+/// Version of the `CaptureStatus` payload written by this crate. Later
+/// versions only append fields, so readers parse the prefix they know.
+pub const CAPTURE_STATUS_VERSION: u8 = 1;
+
+/// Topic: `Audio` chunks
+pub const TOPIC_AUDIO: u32 = 1 << 0;
+/// Topic: `SpeechStarted` / `SpeechStopped` edges
+pub const TOPIC_SPEECH: u32 = 1 << 1;
+/// Topic: `WakewordDetected` and the `PrerollAudio` burst that follows it
+pub const TOPIC_WAKEWORD: u32 = 1 << 2;
+/// Topic: `UtteranceComplete` after each wake word
+pub const TOPIC_UTTERANCE: u32 = 1 << 3;
+/// What a consumer receives before it sends `Subscribe`
+pub const DEFAULT_TOPICS: u32 = TOPIC_AUDIO | TOPIC_WAKEWORD;
+
+/// Version of the `Audio` VAD probability extension written by this crate
+pub const VAD_PROBABILITIES_VERSION: u8 = 1;
+
+/// Speech probability of one VAD window, appended to an `Audio` payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadProbability {
+    /// Capture sample index of the first sample of the 512-sample window. A
+    /// window that started in the previous chunk has an earlier index.
+    pub sample_index: u64,
+    /// Speech probability (0.0 to 1.0), before any threshold or hysteresis
+    pub probability: f32,
+}
+
+impl VadProbability {
+    /// Append as `[version: u8][count: u16][(sample_index: u64, probability: f32)...]`
+    fn write_all(windows: &[Self], bytes: &mut Vec<u8>) {
+        let windows = &windows[..windows.len().min(u16::MAX as usize)];
+        bytes.push(VAD_PROBABILITIES_VERSION);
+        bytes.extend_from_slice(&(windows.len() as u16).to_le_bytes());
+        for window in windows {
+            bytes.extend_from_slice(&window.sample_index.to_le_bytes());
+            bytes.extend_from_slice(&window.probability.to_le_bytes());
+        }
+    }
+
+    /// Parse an extension written by `write_all`. Returns `Ok(None)` for a
+    /// newer extension version, like `WakewordDetails::read`.
+    fn read_all(ext: &[u8]) -> Result<Option<Vec<Self>>, ProtocolError> {
+        if ext[0] != VAD_PROBABILITIES_VERSION {
+            return Ok(None);
+        }
+        if ext.len() < 3 {
+            // minimum: u8 + u16
+            return Err(ProtocolError::InvalidPayloadSize(ext.len() as u32));
+        }
+
+        let count = u16::from_le_bytes([ext[1], ext[2]]) as usize;
+        if ext.len() < 3 + count * 12 {
+            return Err(ProtocolError::InvalidPayloadSize(ext.len() as u32));
+        }
+        let windows = ext[3..3 + count * 12]
+            .chunks_exact(12)
+            .map(|b| Self {
+                sample_index: u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]),
+                probability: f32::from_le_bytes([b[8], b[9], b[10], b[11]]),
+            })
+            .collect();
+
+        Ok(Some(windows))
+    }
+}
+
+/// Version of the `WakewordDetected` details extension written by this crate
+pub const WAKEWORD_DETAILS_VERSION: u8 = 1;
+
+/// Detection details appended to a `WakewordDetected` payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakewordDetails {
+    /// Confidence of the prediction that fired
+    pub confidence: f32,
+    /// Threshold the confidence was compared against
+    pub threshold: f32,
+    /// Capture sample index of the first sample of the chunk that fired
+    /// (16kHz, counted from the start of capture)
+    pub sample_index: u64,
+    /// Recent scores of the firing model, oldest first, ending with the firing score
+    pub scores: Vec<f32>,
+    /// Capture channel the wake word fired on. Trails the scores, so readers
+    /// that predate it ignore it; absent from older servers.
+    pub channel: Option<u16>,
+}
+
+impl WakewordDetails {
+    /// Append as `[version: u8][confidence: f32][threshold: f32][sample_index: u64][score_count: u16][scores: f32...][channel: u16]?`
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let scores = &self.scores[..self.scores.len().min(u16::MAX as usize)];
+        bytes.push(WAKEWORD_DETAILS_VERSION);
+        bytes.extend_from_slice(&self.confidence.to_le_bytes());
+        bytes.extend_from_slice(&self.threshold.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_index.to_le_bytes());
+        bytes.extend_from_slice(&(scores.len() as u16).to_le_bytes());
+        for score in scores {
+            bytes.extend_from_slice(&score.to_le_bytes());
+        }
+        if let Some(channel) = self.channel {
+            bytes.extend_from_slice(&channel.to_le_bytes());
+        }
+    }
+
+    /// Parse an extension written by `write`. Returns `Ok(None)` for a newer
+    /// extension version so old clients keep working against newer servers.
+    fn read(ext: &[u8]) -> Result<Option<Self>, ProtocolError> {
+        if ext[0] != WAKEWORD_DETAILS_VERSION {
+            return Ok(None);
+        }
+        if ext.len() < 19 {
+            // minimum: u8 + f32 + f32 + u64 + u16
+            return Err(ProtocolError::InvalidPayloadSize(ext.len() as u32));
+        }
+
+        let confidence = f32::from_le_bytes([ext[1], ext[2], ext[3], ext[4]]);
+        let threshold = f32::from_le_bytes([ext[5], ext[6], ext[7], ext[8]]);
+        let sample_index = u64::from_le_bytes([
+            ext[9], ext[10], ext[11], ext[12], ext[13], ext[14], ext[15], ext[16],
+        ]);
+        let score_count = u16::from_le_bytes([ext[17], ext[18]]) as usize;
+
+        if ext.len() < 19 + score_count * 4 {
+            return Err(ProtocolError::InvalidPayloadSize(ext.len() as u32));
+        }
+        let scores_end = 19 + score_count * 4;
+        let scores = ext[19..scores_end]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let channel = ext
+            .get(scores_end..scores_end + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]));
+
+        Ok(Some(Self {
+            confidence,
+            threshold,
+            sample_index,
+            scores,
+            channel,
+        }))
+    }
 }
 
 /// Producer protocol messages
@@ -101,6 +491,20 @@ pub enum ProducerMessage {
         timestamp: u64,
         stream_id: u64, // Must match stream_id from Play messages
     },
+    /// Format of the `Play` data of `stream_id`, sent before its first
+    /// chunk. Streams without one are mono 48kHz s16le.
+    StreamFormat {
+        stream_id: u64,
+        sample_rate: u32,
+        channels: u16, // Interleaved
+        encoding: AudioEncoding,
+    },
+
+    /// Handshake: the client's offer, answered with the negotiated session
+    Hello {
+        version: u16,
+        capabilities: u32, // CAP_* flags
+    },
 
     // Audio Crate → Client
     Error {
@@ -111,6 +515,57 @@ pub enum ProducerMessage {
     },
 }
 
+/// Sample encoding of `Play` data, declared with `StreamFormat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AudioEncoding {
+    /// Signed 16-bit little-endian PCM
+    S16le = 0,
+    /// 32-bit little-endian float PCM in -1.0..=1.0
+    F32le = 1,
+    /// Opus packets; reserved, not decoded by this crate yet
+    Opus = 2,
+}
+
+impl TryFrom<u8> for AudioEncoding {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, ProtocolError> {
+        match value {
+            0 => Ok(AudioEncoding::S16le),
+            1 => Ok(AudioEncoding::F32le),
+            2 => Ok(AudioEncoding::Opus),
+            _ => Err(ProtocolError::InvalidAudioEncoding(value)),
+        }
+    }
+}
+
+impl std::str::FromStr for AudioEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "s16le" => Ok(AudioEncoding::S16le),
+            "f32le" => Ok(AudioEncoding::F32le),
+            "opus" => Ok(AudioEncoding::Opus),
+            other => Err(format!(
+                "unknown audio encoding '{}' (expected s16le, f32le or opus)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AudioEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioEncoding::S16le => write!(f, "s16le"),
+            AudioEncoding::F32le => write!(f, "f32le"),
+            AudioEncoding::Opus => write!(f, "opus"),
+        }
+    }
+}
+
 impl ConsumerMessage {
     /// Get current timestamp in milliseconds since epoch
     pub fn current_timestamp() -> u64 {
@@ -125,6 +580,28 @@ impl ConsumerMessage {
         let mut bytes = Vec::new();
 
         match self {
+            ConsumerMessage::ReloadWakeword { models, detection } => {
+                bytes.push(ConsumerMessageType::ReloadWakeword as u8);
+                // Payload: [models: string list][detection: string list]
+                let mut payload = Vec::new();
+                write_string_list(&mut payload, models);
+                write_string_list(&mut payload, detection);
+                bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&payload);
+            }
+            ConsumerMessage::Subscribe { topics } => {
+                bytes.push(ConsumerMessageType::Subscribe as u8);
+                // Payload: [topics: u32]
+                bytes.extend_from_slice(&4u32.to_le_bytes());
+                bytes.extend_from_slice(&topics.to_le_bytes());
+            }
+            ConsumerMessage::Hello {
+                version,
+                capabilities,
+            } => {
+                bytes.push(ConsumerMessageType::Hello as u8);
+                write_hello(&mut bytes, *version, *capabilities);
+            }
             ConsumerMessage::Error { message } => {
                 bytes.push(ConsumerMessageType::Error as u8);
                 let msg_bytes = message.as_bytes();
@@ -135,32 +612,119 @@ impl ConsumerMessage {
                 data,
                 speech_detected,
                 timestamp,
+                vad,
             } => {
                 bytes.push(ConsumerMessageType::Audio as u8);
-                // Payload: [timestamp: u64][speech_detected: u8][data_length: u32][data: bytes]
-                let payload_len = 8 + 1 + 4 + data.len();
-                bytes.extend_from_slice(&(payload_len as u32).to_le_bytes());
+                // Payload: [timestamp: u64][speech_detected: u8][data_length: u32][data: bytes][vad?]
+                // Older clients stop reading after data_length bytes, so the
+                // VAD extension is simply appended.
+                let mut payload = Vec::with_capacity(8 + 1 + 4 + data.len());
+                payload.extend_from_slice(&timestamp.to_le_bytes());
+                payload.push(if *speech_detected { 1u8 } else { 0u8 });
+                payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                payload.extend_from_slice(data);
+                if let Some(vad) = vad {
+                    VadProbability::write_all(vad, &mut payload);
+                }
+                bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&payload);
+            }
+            ConsumerMessage::SpeechStarted {
+                timestamp,
+                sample_index,
+            }
+            | ConsumerMessage::SpeechStopped {
+                timestamp,
+                sample_index,
+            } => {
+                let msg_type = if matches!(self, ConsumerMessage::SpeechStarted { .. }) {
+                    ConsumerMessageType::SpeechStarted
+                } else {
+                    ConsumerMessageType::SpeechStopped
+                };
+                bytes.push(msg_type as u8);
+                // Payload: [timestamp: u64][sample_index: u64]
+                bytes.extend_from_slice(&16u32.to_le_bytes()); // payload size: 2x u64
                 bytes.extend_from_slice(&timestamp.to_le_bytes());
-                bytes.push(if *speech_detected { 1u8 } else { 0u8 });
-                bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
-                bytes.extend_from_slice(data);
+                bytes.extend_from_slice(&sample_index.to_le_bytes());
             }
             ConsumerMessage::WakewordDetected {
                 model,
                 timestamp,
                 spotify_was_paused,
                 mpv_was_paused,
+                details,
             } => {
                 bytes.push(ConsumerMessageType::WakewordDetected as u8);
-                // Payload: [timestamp: u64][spotify_was_paused: u8][mpv_was_paused: u8][model_len: u32][model: bytes]
+                // Payload: [timestamp: u64][spotify_was_paused: u8][mpv_was_paused: u8][model_len: u32][model: bytes][details?]
+                // Older clients stop reading after the model name, so the
+                // details extension is simply appended.
                 let model_bytes = model.as_bytes();
-                let payload_len = 8 + 1 + 1 + 4 + model_bytes.len();
+                let mut payload = Vec::with_capacity(8 + 1 + 1 + 4 + model_bytes.len());
+                payload.extend_from_slice(&timestamp.to_le_bytes());
+                payload.push(if *spotify_was_paused { 1u8 } else { 0u8 });
+                payload.push(if *mpv_was_paused { 1u8 } else { 0u8 });
+                payload.extend_from_slice(&(model_bytes.len() as u32).to_le_bytes());
+                payload.extend_from_slice(model_bytes);
+                if let Some(details) = details {
+                    details.write(&mut payload);
+                }
+                bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&payload);
+            }
+            ConsumerMessage::WakewordReloaded { success, message } => {
+                bytes.push(ConsumerMessageType::WakewordReloaded as u8);
+                // Payload: [success: u8][message: bytes]
+                let msg_bytes = message.as_bytes();
+                bytes.extend_from_slice(&(1 + msg_bytes.len() as u32).to_le_bytes());
+                bytes.push(if *success { 1u8 } else { 0u8 });
+                bytes.extend_from_slice(msg_bytes);
+            }
+            ConsumerMessage::PrerollAudio {
+                data,
+                timestamp,
+                index,
+                count,
+            } => {
+                bytes.push(ConsumerMessageType::PrerollAudio as u8);
+                // Payload: [timestamp: u64][index: u16][count: u16][data_length: u32][data: bytes]
+                let payload_len = 8 + 2 + 2 + 4 + data.len();
                 bytes.extend_from_slice(&(payload_len as u32).to_le_bytes());
                 bytes.extend_from_slice(&timestamp.to_le_bytes());
-                bytes.push(if *spotify_was_paused { 1u8 } else { 0u8 });
-                bytes.push(if *mpv_was_paused { 1u8 } else { 0u8 });
-                bytes.extend_from_slice(&(model_bytes.len() as u32).to_le_bytes());
-                bytes.extend_from_slice(model_bytes);
+                bytes.extend_from_slice(&index.to_le_bytes());
+                bytes.extend_from_slice(&count.to_le_bytes());
+                bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(data);
+            }
+            ConsumerMessage::UtteranceComplete {
+                timestamp,
+                start_sample,
+                end_sample,
+                reason,
+            } => {
+                bytes.push(ConsumerMessageType::UtteranceComplete as u8);
+                // Payload: [timestamp: u64][start_sample: u64][end_sample: u64][reason: u8]
+                bytes.extend_from_slice(&25u32.to_le_bytes()); // payload size: 3x u64 + u8
+                bytes.extend_from_slice(&timestamp.to_le_bytes());
+                bytes.extend_from_slice(&start_sample.to_le_bytes());
+                bytes.extend_from_slice(&end_sample.to_le_bytes());
+                bytes.push(*reason as u8);
+            }
+            ConsumerMessage::CaptureStatus {
+                lost,
+                deaf_ms,
+                reason,
+            } => {
+                bytes.push(ConsumerMessageType::CaptureStatus as u8);
+                // Payload: [version: u8][lost: u8][deaf_ms: u64][reason_len: u32][reason: bytes]
+                let reason_bytes = reason.as_bytes();
+                let payload_len = 1 + 1 + 8 + 4 + reason_bytes.len();
+                bytes.extend_from_slice(&(payload_len as u32).to_le_bytes());
+                bytes.push(CAPTURE_STATUS_VERSION);
+                bytes.push(if *lost { 1u8 } else { 0u8 });
+                bytes.extend_from_slice(&deaf_ms.to_le_bytes());
+                bytes.extend_from_slice(&(reason_bytes.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(reason_bytes);
             }
         }
 
@@ -173,11 +737,73 @@ impl ConsumerMessage {
         payload: &[u8],
     ) -> Result<Self, ProtocolError> {
         match msg_type {
+            ConsumerMessageType::ReloadWakeword => {
+                let (models, used) = read_string_list(payload)?;
+                let (detection, _) = read_string_list(&payload[used..])?;
+                Ok(ConsumerMessage::ReloadWakeword { models, detection })
+            }
+            ConsumerMessageType::Subscribe => {
+                if payload.len() != 4 {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+                let topics = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                Ok(ConsumerMessage::Subscribe { topics })
+            }
+            ConsumerMessageType::Hello => {
+                let (version, capabilities) = read_hello(payload)?;
+                Ok(ConsumerMessage::Hello {
+                    version,
+                    capabilities,
+                })
+            }
+            ConsumerMessageType::SpeechStarted | ConsumerMessageType::SpeechStopped => {
+                // Payload: [timestamp: u64][sample_index: u64]
+                if payload.len() != 16 {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                let timestamp = u64::from_le_bytes([
+                    payload[0], payload[1], payload[2], payload[3], payload[4], payload[5],
+                    payload[6], payload[7],
+                ]);
+                let sample_index = u64::from_le_bytes([
+                    payload[8],
+                    payload[9],
+                    payload[10],
+                    payload[11],
+                    payload[12],
+                    payload[13],
+                    payload[14],
+                    payload[15],
+                ]);
+
+                if msg_type == ConsumerMessageType::SpeechStarted {
+                    Ok(ConsumerMessage::SpeechStarted {
+                        timestamp,
+                        sample_index,
+                    })
+                } else {
+                    Ok(ConsumerMessage::SpeechStopped {
+                        timestamp,
+                        sample_index,
+                    })
+                }
+            }
             ConsumerMessageType::Error => {
                 let message = String::from_utf8(payload.to_vec())
                     .map_err(|_| ProtocolError::Utf8(std::str::from_utf8(payload).unwrap_err()))?;
                 Ok(ConsumerMessage::Error { message })
             }
+            ConsumerMessageType::WakewordReloaded => {
+                if payload.is_empty() {
+                    return Err(ProtocolError::InvalidPayloadSize(0));
+                }
+                let message = std::str::from_utf8(&payload[1..])?.to_string();
+                Ok(ConsumerMessage::WakewordReloaded {
+                    success: payload[0] != 0,
+                    message,
+                })
+            }
             ConsumerMessageType::Audio => {
                 if payload.len() < 13 {
                     // minimum: u64 + u8 + u32
@@ -198,10 +824,18 @@ impl ConsumerMessage {
                 }
 
                 let data = payload[13..13 + data_length].to_vec();
+                let ext = &payload[13 + data_length..];
+                let vad = if ext.is_empty() {
+                    None
+                } else {
+                    VadProbability::read_all(ext)?
+                };
+
                 Ok(ConsumerMessage::Audio {
                     data,
                     speech_detected,
                     timestamp,
+                    vad,
                 })
             }
             ConsumerMessageType::WakewordDetected => {
@@ -233,17 +867,142 @@ impl ConsumerMessage {
                         )
                     })?;
 
+                let ext = &payload[14 + model_len..];
+                let details = if ext.is_empty() {
+                    None
+                } else {
+                    WakewordDetails::read(ext)?
+                };
+
                 Ok(ConsumerMessage::WakewordDetected {
                     model,
                     timestamp,
                     spotify_was_paused,
                     mpv_was_paused,
+                    details,
+                })
+            }
+            ConsumerMessageType::PrerollAudio => {
+                if payload.len() < 16 {
+                    // minimum: u64 + u16 + u16 + u32
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                let timestamp = u64::from_le_bytes([
+                    payload[0], payload[1], payload[2], payload[3], payload[4], payload[5],
+                    payload[6], payload[7],
+                ]);
+                let index = u16::from_le_bytes([payload[8], payload[9]]);
+                let count = u16::from_le_bytes([payload[10], payload[11]]);
+                let data_length =
+                    u32::from_le_bytes([payload[12], payload[13], payload[14], payload[15]])
+                        as usize;
+
+                if payload.len() < 16 + data_length {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                Ok(ConsumerMessage::PrerollAudio {
+                    data: payload[16..16 + data_length].to_vec(),
+                    timestamp,
+                    index,
+                    count,
+                })
+            }
+            ConsumerMessageType::UtteranceComplete => {
+                // Payload: [timestamp: u64][start_sample: u64][end_sample: u64][reason: u8]
+                if payload.len() != 25 {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                let read_u64 = |pos: usize| {
+                    let mut b = [0u8; 8];
+                    b.copy_from_slice(&payload[pos..pos + 8]);
+                    u64::from_le_bytes(b)
+                };
+                Ok(ConsumerMessage::UtteranceComplete {
+                    timestamp: read_u64(0),
+                    start_sample: read_u64(8),
+                    end_sample: read_u64(16),
+                    reason: UtteranceEndReason::try_from(payload[24])?,
+                })
+            }
+            ConsumerMessageType::CaptureStatus => {
+                // Payload: [version: u8][lost: u8][deaf_ms: u64][reason_len: u32][reason: bytes]
+                if payload.len() < 14 {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                let mut deaf_ms = [0u8; 8];
+                deaf_ms.copy_from_slice(&payload[2..10]);
+                let reason_len =
+                    u32::from_le_bytes([payload[10], payload[11], payload[12], payload[13]])
+                        as usize;
+                if payload.len() < 14 + reason_len {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+                let reason = std::str::from_utf8(&payload[14..14 + reason_len])?.to_string();
+
+                Ok(ConsumerMessage::CaptureStatus {
+                    lost: payload[1] != 0,
+                    deaf_ms: u64::from_le_bytes(deaf_ms),
+                    reason,
                 })
             }
         }
     }
 }
 
+/// Append a Hello as `[payload_len: u32][version: u16][capabilities: u32]`
+fn write_hello(bytes: &mut Vec<u8>, version: u16, capabilities: u32) {
+    bytes.extend_from_slice(&6u32.to_le_bytes()); // payload size: u16 + u32
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&capabilities.to_le_bytes());
+}
+
+/// Parse a Hello payload. Trailing bytes are ignored so later versions can extend it.
+fn read_hello(payload: &[u8]) -> Result<(u16, u32), ProtocolError> {
+    if payload.len() < 6 {
+        return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+    }
+    let version = u16::from_le_bytes([payload[0], payload[1]]);
+    let capabilities = u32::from_le_bytes([payload[2], payload[3], payload[4], payload[5]]);
+    Ok((version, capabilities))
+}
+
+/// Append a string list as `[count: u32]` followed by `[len: u32][utf8 bytes]` per item
+fn write_string_list(bytes: &mut Vec<u8>, items: &[String]) {
+    bytes.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    for item in items {
+        bytes.extend_from_slice(&(item.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(item.as_bytes());
+    }
+}
+
+/// Parse a string list written by `write_string_list`, returning it and the number of bytes used
+fn read_string_list(payload: &[u8]) -> Result<(Vec<String>, usize), ProtocolError> {
+    let read_u32 = |pos: usize| -> Result<usize, ProtocolError> {
+        payload
+            .get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or(ProtocolError::InvalidPayloadSize(payload.len() as u32))
+    };
+
+    let count = read_u32(0)?;
+    let mut pos = 4;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        let len = read_u32(pos)?;
+        pos += 4;
+        let item = payload
+            .get(pos..pos + len)
+            .ok_or(ProtocolError::InvalidPayloadSize(payload.len() as u32))?;
+        items.push(std::str::from_utf8(item)?.to_string());
+        pos += len;
+    }
+    Ok((items, pos))
+}
+
 impl ProducerMessage {
     /// Get current timestamp in milliseconds since epoch
     pub fn current_timestamp() -> u64 {
@@ -276,6 +1035,27 @@ impl ProducerMessage {
                 bytes.extend_from_slice(&timestamp.to_le_bytes());
                 bytes.extend_from_slice(&stream_id.to_le_bytes());
             }
+            ProducerMessage::StreamFormat {
+                stream_id,
+                sample_rate,
+                channels,
+                encoding,
+            } => {
+                bytes.push(ProducerMessageType::StreamFormat as u8);
+                // Payload: [stream_id: u64][sample_rate: u32][channels: u16][encoding: u8]
+                bytes.extend_from_slice(&15u32.to_le_bytes());
+                bytes.extend_from_slice(&stream_id.to_le_bytes());
+                bytes.extend_from_slice(&sample_rate.to_le_bytes());
+                bytes.extend_from_slice(&channels.to_le_bytes());
+                bytes.push(*encoding as u8);
+            }
+            ProducerMessage::Hello {
+                version,
+                capabilities,
+            } => {
+                bytes.push(ProducerMessageType::Hello as u8);
+                write_hello(&mut bytes, *version, *capabilities);
+            }
             ProducerMessage::Error { message } => {
                 bytes.push(ProducerMessageType::Error as u8);
                 let msg_bytes = message.as_bytes();
@@ -340,6 +1120,35 @@ impl ProducerMessage {
                     stream_id,
                 })
             }
+            ProducerMessageType::StreamFormat => {
+                // Payload: [stream_id: u64][sample_rate: u32][channels: u16][encoding: u8]
+                if payload.len() != 15 {
+                    return Err(ProtocolError::InvalidPayloadSize(payload.len() as u32));
+                }
+
+                let stream_id = u64::from_le_bytes([
+                    payload[0], payload[1], payload[2], payload[3], payload[4], payload[5],
+                    payload[6], payload[7],
+                ]);
+                let sample_rate =
+                    u32::from_le_bytes([payload[8], payload[9], payload[10], payload[11]]);
+                let channels = u16::from_le_bytes([payload[12], payload[13]]);
+                let encoding = AudioEncoding::try_from(payload[14])?;
+
+                Ok(ProducerMessage::StreamFormat {
+                    stream_id,
+                    sample_rate,
+                    channels,
+                    encoding,
+                })
+            }
+            ProducerMessageType::Hello => {
+                let (version, capabilities) = read_hello(payload)?;
+                Ok(ProducerMessage::Hello {
+                    version,
+                    capabilities,
+                })
+            }
             ProducerMessageType::Error => {
                 let message = String::from_utf8(payload.to_vec())
                     .map_err(|_| ProtocolError::Utf8(std::str::from_utf8(payload).unwrap_err()))?;
@@ -364,11 +1173,72 @@ impl ProducerMessage {
 /// Binary protocol connection for consumers
 pub struct ConsumerConnection<T: Read + Write> {
     stream: T,
+    session: Session,
 }
 
 impl<T: Read + Write> ConsumerConnection<T> {
     pub fn new(stream: T) -> Self {
-        Self { stream }
+        Self {
+            stream,
+            session: Session::default(),
+        }
+    }
+
+    /// Session agreed with the peer; messages it can't handle are down-converted on write
+    pub fn session(&self) -> Session {
+        self.session
+    }
+
+    pub fn set_session(&mut self, session: Session) {
+        self.session = session;
+    }
+
+    /// Client side: offer `capabilities` and wait for the server's answer
+    pub fn handshake(&mut self, capabilities: u32) -> Result<Session, ProtocolError> {
+        self.write_message(&ConsumerMessage::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities,
+        })?;
+        match self.read_message()? {
+            ConsumerMessage::Hello {
+                version,
+                capabilities: granted,
+            } => {
+                self.session = Session::negotiate(version, granted, capabilities)?;
+                Ok(self.session)
+            }
+            ConsumerMessage::Error { message } => Err(ProtocolError::HandshakeRejected(message)),
+            other => Err(ProtocolError::UnexpectedHandshakeMessage(format!(
+                "{:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Server side: answer a client's `Hello` with the negotiated session, or
+    /// with an `Error` explaining the mismatch before failing
+    pub fn accept_hello(
+        &mut self,
+        version: u16,
+        capabilities: u32,
+        local_capabilities: u32,
+    ) -> Result<Session, ProtocolError> {
+        match Session::negotiate(version, capabilities, local_capabilities) {
+            Ok(session) => {
+                self.write_message(&ConsumerMessage::Hello {
+                    version: session.version,
+                    capabilities: session.capabilities,
+                })?;
+                self.session = session;
+                Ok(session)
+            }
+            Err(e) => {
+                self.write_message(&ConsumerMessage::Error {
+                    message: e.to_string(),
+                })?;
+                Err(e)
+            }
+        }
     }
 
     /// Read a consumer message from the connection
@@ -399,9 +1269,56 @@ impl<T: Read + Write> ConsumerConnection<T> {
         ConsumerMessage::from_bytes(message_type, &payload)
     }
 
-    /// Write a consumer message to the connection
+    /// Write a consumer message to the connection. Pre-roll is dropped and
+    /// wakeword details and VAD probabilities are stripped for peers that
+    /// didn't negotiate them; those peers get a lost capture as an `Error`
+    /// and never hear that it was restored.
     pub fn write_message(&mut self, message: &ConsumerMessage) -> Result<(), ProtocolError> {
-        let bytes = message.to_bytes()?;
+        let bytes = match message {
+            ConsumerMessage::PrerollAudio { .. } if !self.session.supports(CAP_PREROLL) => {
+                return Ok(());
+            }
+            ConsumerMessage::CaptureStatus { lost, reason, .. }
+                if !self.session.supports(CAP_CAPTURE_STATUS) =>
+            {
+                if !lost {
+                    return Ok(());
+                }
+                ConsumerMessage::Error {
+                    message: format!("Audio capture lost, retrying: {}", reason),
+                }
+                .to_bytes()?
+            }
+            ConsumerMessage::Audio {
+                data,
+                speech_detected,
+                timestamp,
+                vad: Some(_),
+            } if !self.session.supports(CAP_VAD_PROBABILITIES) => ConsumerMessage::Audio {
+                data: data.clone(),
+                speech_detected: *speech_detected,
+                timestamp: *timestamp,
+                vad: None,
+            }
+            .to_bytes()?,
+            ConsumerMessage::WakewordDetected {
+                model,
+                timestamp,
+                spotify_was_paused,
+                mpv_was_paused,
+                details: Some(_),
+            } if !self.session.supports(CAP_WAKEWORD_DETAILS) => {
+                ConsumerMessage::WakewordDetected {
+                    model: model.clone(),
+                    timestamp: *timestamp,
+                    spotify_was_paused: *spotify_was_paused,
+                    mpv_was_paused: *mpv_was_paused,
+                    details: None,
+                }
+                .to_bytes()?
+            }
+            _ => message.to_bytes()?,
+        };
         self.stream.write_all(&bytes)?;
         Ok(())
     }
@@ -410,11 +1327,72 @@ impl<T: Read + Write> ConsumerConnection<T> {
 /// Binary protocol connection for producers
 pub struct ProducerConnection<T: Read + Write> {
     stream: T,
+    session: Session,
 }
 
 impl<T: Read + Write> ProducerConnection<T> {
     pub fn new(stream: T) -> Self {
-        Self { stream }
+        Self {
+            stream,
+            session: Session::default(),
+        }
+    }
+
+    /// Session agreed with the peer
+    pub fn session(&self) -> Session {
+        self.session
+    }
+
+    pub fn set_session(&mut self, session: Session) {
+        self.session = session;
+    }
+
+    /// Client side: offer `capabilities` and wait for the server's answer
+    pub fn handshake(&mut self, capabilities: u32) -> Result<Session, ProtocolError> {
+        self.write_message(&ProducerMessage::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities,
+        })?;
+        match self.read_message()? {
+            ProducerMessage::Hello {
+                version,
+                capabilities: granted,
+            } => {
+                self.session = Session::negotiate(version, granted, capabilities)?;
+                Ok(self.session)
+            }
+            ProducerMessage::Error { message } => Err(ProtocolError::HandshakeRejected(message)),
+            other => Err(ProtocolError::UnexpectedHandshakeMessage(format!(
+                "{:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Server side: answer a client's `Hello` with the negotiated session, or
+    /// with an `Error` explaining the mismatch before failing
+    pub fn accept_hello(
+        &mut self,
+        version: u16,
+        capabilities: u32,
+        local_capabilities: u32,
+    ) -> Result<Session, ProtocolError> {
+        match Session::negotiate(version, capabilities, local_capabilities) {
+            Ok(session) => {
+                self.write_message(&ProducerMessage::Hello {
+                    version: session.version,
+                    capabilities: session.capabilities,
+                })?;
+                self.session = session;
+                Ok(session)
+            }
+            Err(e) => {
+                self.write_message(&ProducerMessage::Error {
+                    message: e.to_string(),
+                })?;
+                Err(e)
+            }
+        }
     }
 
     /// Read a producer message from the connection
@@ -488,6 +1466,7 @@ mod tests {
             timestamp: 1234567890,
             spotify_was_paused: true,
             mpv_was_paused: false,
+            details: None,
         };
         let bytes = msg.to_bytes().unwrap();
 
@@ -505,16 +1484,482 @@ mod tests {
                 timestamp,
                 spotify_was_paused,
                 mpv_was_paused,
+                details,
             } => {
                 assert_eq!(model, "hey-jarvis");
                 assert_eq!(timestamp, 1234567890);
                 assert_eq!(spotify_was_paused, true);
                 assert_eq!(mpv_was_paused, false);
+                assert_eq!(details, None);
             }
             _ => panic!("Expected WakewordDetected message"),
         }
     }
 
+    #[test]
+    fn test_wakeword_detected_details_binary() {
+        let details = WakewordDetails {
+            confidence: 0.87,
+            threshold: 0.5,
+            sample_index: 16000 * 3600,
+            scores: vec![0.0, 0.12, 0.43, 0.87],
+            channel: Some(2),
+        };
+        let msg = ConsumerMessage::WakewordDetected {
+            model: "hey_mycroft".to_string(),
+            timestamp: 1234567890,
+            spotify_was_paused: false,
+            mpv_was_paused: true,
+            details: Some(details.clone()),
+        };
+        let bytes = msg.to_bytes().unwrap();
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes.clone()));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::WakewordDetected {
+                model,
+                details: parsed,
+                ..
+            } => {
+                assert_eq!(model, "hey_mycroft");
+                assert_eq!(parsed, Some(details));
+            }
+            _ => panic!("Expected WakewordDetected message"),
+        }
+
+        // An unknown extension version is ignored rather than rejected
+        let mut payload = bytes[5..].to_vec();
+        payload[14 + "hey_mycroft".len()] = WAKEWORD_DETAILS_VERSION + 1;
+        match ConsumerMessage::from_bytes(ConsumerMessageType::WakewordDetected, &payload).unwrap()
+        {
+            ConsumerMessage::WakewordDetected { details, .. } => assert_eq!(details, None),
+            _ => panic!("Expected WakewordDetected message"),
+        }
+
+        // Details from a server without channel reporting still parse
+        let without_channel = &bytes[5..bytes.len() - 2];
+        match ConsumerMessage::from_bytes(ConsumerMessageType::WakewordDetected, without_channel)
+            .unwrap()
+        {
+            ConsumerMessage::WakewordDetected { details, .. } => {
+                assert_eq!(details.unwrap().channel, None)
+            }
+            _ => panic!("Expected WakewordDetected message"),
+        }
+
+        // A truncated score list is an error
+        let truncated = &bytes[5..bytes.len() - 4];
+        assert!(
+            ConsumerMessage::from_bytes(ConsumerMessageType::WakewordDetected, truncated).is_err()
+        );
+    }
+
+    /// Reads from a canned peer reply and records everything written
+    struct Duplex {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hello_negotiation() {
+        // Newer client: version drops to ours, capabilities to the overlap
+        let session = Session::negotiate(
+            PROTOCOL_VERSION + 1,
+            CAP_PREROLL | CAP_VAD_PROBABILITIES,
+            CAP_PREROLL,
+        )
+        .unwrap();
+        assert_eq!(session.version, PROTOCOL_VERSION);
+        assert!(session.supports(CAP_PREROLL));
+        assert!(!session.supports(CAP_VAD_PROBABILITIES));
+        assert_eq!(session.audio_codec(), AudioCodec::Raw);
+
+        // Compressed audio only when both sides offer a codec, ADPCM first
+        let offered = CAP_AUDIO_ULAW | CAP_AUDIO_ADPCM;
+        let ulaw = Session::negotiate(PROTOCOL_VERSION, CAP_AUDIO_ULAW, offered).unwrap();
+        assert_eq!(ulaw.audio_codec(), AudioCodec::Ulaw);
+        let both = Session::negotiate(PROTOCOL_VERSION, offered, offered).unwrap();
+        assert_eq!(both.audio_codec(), AudioCodec::ImaAdpcm);
+
+        // Server side rejects an unsupported version with an Error message
+        let mut server = ConsumerConnection::new(Duplex {
+            input: Cursor::new(Vec::new()),
+            output: Vec::new(),
+        });
+        assert!(matches!(
+            server.accept_hello(0, CAP_PREROLL, CAP_PREROLL),
+            Err(ProtocolError::UnsupportedVersion { peer: 0, .. })
+        ));
+        let mut reply = ConsumerConnection::new(Cursor::new(server.stream.output));
+        match reply.read_message().unwrap() {
+            ConsumerMessage::Error { message } => assert!(message.contains("version 0")),
+            _ => panic!("Expected Error message"),
+        }
+
+        // Client side surfaces the server's rejection
+        let rejection = ProducerMessage::Error {
+            message: "Unsupported protocol version".to_string(),
+        };
+        let mut client = ProducerConnection::new(Duplex {
+            input: Cursor::new(rejection.to_bytes().unwrap()),
+            output: Vec::new(),
+        });
+        assert!(matches!(
+            client.handshake(0),
+            Err(ProtocolError::HandshakeRejected(_))
+        ));
+        assert_eq!(client.stream.output[0], ProducerMessageType::Hello as u8);
+    }
+
+    #[test]
+    fn test_hello_pending_only_waits_for_silent_peers() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hello = ConsumerMessageType::Hello as u8;
+        let timeout = Duration::from_secs(5);
+
+        // Any first byte answers at once, Hello or a legacy message
+        for (first, expected) in [(hello, true), (ConsumerMessageType::Subscribe as u8, false)] {
+            let mut client = TcpStream::connect(addr).unwrap();
+            let (server, _) = listener.accept().unwrap();
+            client.write_all(&[first]).unwrap();
+            let start = std::time::Instant::now();
+            assert_eq!(hello_pending(&server, hello, timeout).unwrap(), expected);
+            assert!(start.elapsed() < timeout);
+        }
+
+        // A peer that sends nothing is legacy once the timeout passes
+        let _client = TcpStream::connect(addr).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let start = std::time::Instant::now();
+        let short = Duration::from_millis(50);
+        assert!(!hello_pending(&server, hello, short).unwrap());
+        assert!(start.elapsed() >= short);
+    }
+
+    #[test]
+    fn test_consumer_handshake_and_downconversion() {
+        let answer = ConsumerMessage::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: CAP_WAKEWORD_DETAILS,
+        };
+        let mut client = ConsumerConnection::new(Duplex {
+            input: Cursor::new(answer.to_bytes().unwrap()),
+            output: Vec::new(),
+        });
+        let session = client
+            .handshake(CAP_PREROLL | CAP_WAKEWORD_DETAILS)
+            .unwrap();
+        assert_eq!(session.version, PROTOCOL_VERSION);
+        assert_eq!(session.capabilities, CAP_WAKEWORD_DETAILS);
+
+        let mut offer = ConsumerConnection::new(Cursor::new(client.stream.output));
+        match offer.read_message().unwrap() {
+            ConsumerMessage::Hello {
+                version,
+                capabilities,
+            } => {
+                assert_eq!(version, PROTOCOL_VERSION);
+                assert_eq!(capabilities, CAP_PREROLL | CAP_WAKEWORD_DETAILS);
+            }
+            _ => panic!("Expected Hello message"),
+        }
+
+        // A v1 peer gets no pre-roll and no details
+        let mut legacy = ConsumerConnection::new(Cursor::new(Vec::new()));
+        legacy.set_session(Session::legacy());
+        legacy
+            .write_message(&ConsumerMessage::PrerollAudio {
+                data: vec![1, 2],
+                timestamp: 1,
+                index: 0,
+                count: 1,
+            })
+            .unwrap();
+        legacy
+            .write_message(&ConsumerMessage::WakewordDetected {
+                model: "hey_mycroft".to_string(),
+                timestamp: 1,
+                spotify_was_paused: false,
+                mpv_was_paused: false,
+                details: Some(WakewordDetails {
+                    confidence: 0.9,
+                    threshold: 0.5,
+                    sample_index: 0,
+                    scores: vec![0.9],
+                    channel: None,
+                }),
+            })
+            .unwrap();
+
+        let bytes = legacy.stream.into_inner();
+        assert_eq!(bytes[0], ConsumerMessageType::WakewordDetected as u8);
+        assert_eq!(bytes.len(), 5 + 14 + "hey_mycroft".len());
+    }
+
+    #[test]
+    fn test_subscribe_and_speech_edges_binary() {
+        let msg = ConsumerMessage::Subscribe {
+            topics: TOPIC_SPEECH | TOPIC_WAKEWORD,
+        };
+        let bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::Subscribe as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::Subscribe { topics } => {
+                assert_eq!(topics, TOPIC_SPEECH | TOPIC_WAKEWORD);
+            }
+            _ => panic!("Expected Subscribe message"),
+        }
+
+        let mut bytes = ConsumerMessage::SpeechStarted {
+            timestamp: 1234567890,
+            sample_index: 16000,
+        }
+        .to_bytes()
+        .unwrap();
+        bytes.extend(
+            ConsumerMessage::SpeechStopped {
+                timestamp: 1234568890,
+                sample_index: 32000,
+            }
+            .to_bytes()
+            .unwrap(),
+        );
+        assert_eq!(bytes[0], ConsumerMessageType::SpeechStarted as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::SpeechStarted {
+                timestamp,
+                sample_index,
+            } => {
+                assert_eq!(timestamp, 1234567890);
+                assert_eq!(sample_index, 16000);
+            }
+            _ => panic!("Expected SpeechStarted message"),
+        }
+        match connection.read_message().unwrap() {
+            ConsumerMessage::SpeechStopped {
+                timestamp,
+                sample_index,
+            } => {
+                assert_eq!(timestamp, 1234568890);
+                assert_eq!(sample_index, 32000);
+            }
+            _ => panic!("Expected SpeechStopped message"),
+        }
+    }
+
+    #[test]
+    fn test_utterance_complete_binary() {
+        let msg = ConsumerMessage::UtteranceComplete {
+            timestamp: 1234567890,
+            start_sample: 16000,
+            end_sample: 48000,
+            reason: UtteranceEndReason::MaxLength,
+        };
+        let mut bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::UtteranceComplete as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes.clone()));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::UtteranceComplete {
+                timestamp,
+                start_sample,
+                end_sample,
+                reason,
+            } => {
+                assert_eq!(timestamp, 1234567890);
+                assert_eq!(start_sample, 16000);
+                assert_eq!(end_sample, 48000);
+                assert_eq!(reason, UtteranceEndReason::MaxLength);
+            }
+            _ => panic!("Expected UtteranceComplete message"),
+        }
+
+        // Unknown reason
+        *bytes.last_mut().unwrap() = 9;
+        assert!(matches!(
+            ConsumerMessage::from_bytes(ConsumerMessageType::UtteranceComplete, &bytes[5..]),
+            Err(ProtocolError::InvalidUtteranceEndReason(9))
+        ));
+    }
+
+    #[test]
+    fn test_capture_status_binary() {
+        let msg = ConsumerMessage::CaptureStatus {
+            lost: true,
+            deaf_ms: 0,
+            reason: "device unplugged".to_string(),
+        };
+        let bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::CaptureStatus as u8);
+        assert_eq!(bytes[5], CAPTURE_STATUS_VERSION);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes.clone()));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::CaptureStatus {
+                lost,
+                deaf_ms,
+                reason,
+            } => {
+                assert!(lost);
+                assert_eq!(deaf_ms, 0);
+                assert_eq!(reason, "device unplugged");
+            }
+            _ => panic!("Expected CaptureStatus message"),
+        }
+
+        // A later version may append fields
+        let mut payload = bytes[5..].to_vec();
+        payload[0] = CAPTURE_STATUS_VERSION + 1;
+        payload.extend_from_slice(&[1, 2, 3]);
+        assert!(matches!(
+            ConsumerMessage::from_bytes(ConsumerMessageType::CaptureStatus, &payload),
+            Ok(ConsumerMessage::CaptureStatus { lost: true, .. })
+        ));
+
+        // Truncated reason
+        assert!(matches!(
+            ConsumerMessage::from_bytes(
+                ConsumerMessageType::CaptureStatus,
+                &bytes[5..bytes.len() - 1]
+            ),
+            Err(ProtocolError::InvalidPayloadSize(_))
+        ));
+    }
+
+    #[test]
+    fn test_capture_status_downconversion() {
+        let lost = ConsumerMessage::CaptureStatus {
+            lost: true,
+            deaf_ms: 0,
+            reason: "device unplugged".to_string(),
+        };
+        let restored = ConsumerMessage::CaptureStatus {
+            lost: false,
+            deaf_ms: 2500,
+            reason: String::new(),
+        };
+
+        // Without the capability the loss is an Error and the restore is dropped
+        let mut legacy = ConsumerConnection::new(Cursor::new(Vec::new()));
+        legacy.set_session(Session::legacy());
+        legacy.write_message(&lost).unwrap();
+        legacy.write_message(&restored).unwrap();
+        let mut reader = ConsumerConnection::new(Cursor::new(legacy.stream.into_inner()));
+        match reader.read_message().unwrap() {
+            ConsumerMessage::Error { message } => {
+                assert_eq!(message, "Audio capture lost, retrying: device unplugged")
+            }
+            other => panic!("Expected Error message, got {:?}", other),
+        }
+        assert!(reader.read_message().is_err());
+
+        let mut current = ConsumerConnection::new(Cursor::new(Vec::new()));
+        current.set_session(
+            Session::negotiate(PROTOCOL_VERSION, CAP_CAPTURE_STATUS, CAP_CAPTURE_STATUS).unwrap(),
+        );
+        current.write_message(&restored).unwrap();
+        let mut reader = ConsumerConnection::new(Cursor::new(current.stream.into_inner()));
+        assert!(matches!(
+            reader.read_message().unwrap(),
+            ConsumerMessage::CaptureStatus {
+                lost: false,
+                deaf_ms: 2500,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_reload_wakeword_binary() {
+        let msg = ConsumerMessage::ReloadWakeword {
+            models: vec!["hey_mycroft".to_string(), "models/alexa.tflite".to_string()],
+            detection: vec!["alexa:threshold=0.6".to_string()],
+        };
+        let bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::ReloadWakeword as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::ReloadWakeword { models, detection } => {
+                assert_eq!(models, vec!["hey_mycroft", "models/alexa.tflite"]);
+                assert_eq!(detection, vec!["alexa:threshold=0.6"]);
+            }
+            _ => panic!("Expected ReloadWakeword message"),
+        }
+
+        // Truncated list
+        let bad = [2u8, 0, 0, 0, 5, 0, 0, 0, b'a'];
+        assert!(ConsumerMessage::from_bytes(ConsumerMessageType::ReloadWakeword, &bad).is_err());
+    }
+
+    #[test]
+    fn test_wakeword_reloaded_binary() {
+        let msg = ConsumerMessage::WakewordReloaded {
+            success: false,
+            message: "Unknown wake word model 'alexa'".to_string(),
+        };
+        let bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::WakewordReloaded as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::WakewordReloaded { success, message } => {
+                assert!(!success);
+                assert_eq!(message, "Unknown wake word model 'alexa'");
+            }
+            _ => panic!("Expected WakewordReloaded message"),
+        }
+    }
+
+    #[test]
+    fn test_preroll_audio_binary() {
+        let msg = ConsumerMessage::PrerollAudio {
+            data: vec![1, 2, 3, 4],
+            timestamp: 1234567890,
+            index: 2,
+            count: 19,
+        };
+        let bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ConsumerMessageType::PrerollAudio as u8);
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::PrerollAudio {
+                data,
+                timestamp,
+                index,
+                count,
+            } => {
+                assert_eq!(data, vec![1, 2, 3, 4]);
+                assert_eq!(timestamp, 1234567890);
+                assert_eq!(index, 2);
+                assert_eq!(count, 19);
+            }
+            _ => panic!("Expected PrerollAudio message"),
+        }
+    }
+
     #[test]
     fn test_audio_message_binary() {
         let audio_data = vec![1, 2, 3, 4, 5, 6];
@@ -522,6 +1967,7 @@ mod tests {
             data: audio_data.clone(),
             speech_detected: true,
             timestamp: 1234567890,
+            vad: None,
         };
         let bytes = msg.to_bytes().unwrap();
 
@@ -538,13 +1984,74 @@ mod tests {
                 data,
                 speech_detected,
                 timestamp,
+                vad,
             } => {
                 assert_eq!(data, audio_data);
                 assert_eq!(speech_detected, true);
                 assert_eq!(timestamp, 1234567890);
+                assert_eq!(vad, None);
+            }
+            _ => panic!("Expected Audio message"),
+        }
+    }
+
+    #[test]
+    fn test_audio_vad_probabilities_binary() {
+        let windows = vec![
+            VadProbability {
+                sample_index: 16000 - 256,
+                probability: 0.12,
+            },
+            VadProbability {
+                sample_index: 16000 + 256,
+                probability: 0.91,
+            },
+        ];
+        let msg = ConsumerMessage::Audio {
+            data: vec![1, 2, 3, 4],
+            speech_detected: true,
+            timestamp: 1234567890,
+            vad: Some(windows.clone()),
+        };
+        let bytes = msg.to_bytes().unwrap();
+
+        let mut connection = ConsumerConnection::new(Cursor::new(bytes.clone()));
+        match connection.read_message().unwrap() {
+            ConsumerMessage::Audio { data, vad, .. } => {
+                assert_eq!(data, vec![1, 2, 3, 4]);
+                assert_eq!(vad, Some(windows.clone()));
             }
             _ => panic!("Expected Audio message"),
         }
+
+        // An unknown extension version is ignored rather than rejected
+        let mut payload = bytes[5..].to_vec();
+        payload[13 + 4] = VAD_PROBABILITIES_VERSION + 1;
+        match ConsumerMessage::from_bytes(ConsumerMessageType::Audio, &payload).unwrap() {
+            ConsumerMessage::Audio { data, vad, .. } => {
+                assert_eq!(data, vec![1, 2, 3, 4]);
+                assert_eq!(vad, None);
+            }
+            _ => panic!("Expected Audio message"),
+        }
+
+        // A truncated window list is an error
+        let truncated = &bytes[5..bytes.len() - 2];
+        assert!(ConsumerMessage::from_bytes(ConsumerMessageType::Audio, truncated).is_err());
+
+        // Peers that didn't negotiate the extension get plain audio
+        let mut legacy = ConsumerConnection::new(Cursor::new(Vec::new()));
+        legacy.set_session(Session::legacy());
+        legacy.write_message(&msg).unwrap();
+        assert_eq!(legacy.stream.into_inner().len(), 5 + 13 + 4);
+
+        let mut current = ConsumerConnection::new(Cursor::new(Vec::new()));
+        current.set_session(Session {
+            version: PROTOCOL_VERSION,
+            capabilities: CAP_VAD_PROBABILITIES,
+        });
+        current.write_message(&msg).unwrap();
+        assert_eq!(current.stream.into_inner(), bytes);
     }
 
     #[test]
@@ -592,6 +2099,10 @@ mod tests {
             ConsumerMessageType::try_from(0x15).unwrap(),
             ConsumerMessageType::WakewordDetected
         );
+        assert_eq!(
+            ConsumerMessageType::try_from(0x02).unwrap(),
+            ConsumerMessageType::Hello
+        );
         assert!(ConsumerMessageType::try_from(0xFF).is_err());
 
         // Test ProducerMessageType conversions
@@ -612,6 +2123,10 @@ mod tests {
             ProducerMessageType::try_from(0x32).unwrap(),
             ProducerMessageType::PlaybackComplete
         );
+        assert_eq!(
+            ProducerMessageType::try_from(0x24).unwrap(),
+            ProducerMessageType::StreamFormat
+        );
         assert!(ProducerMessageType::try_from(0xFF).is_err());
         assert!(ProducerMessageType::try_from(0x21).is_err()); // Stop no longer valid
     }
@@ -646,6 +2161,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_producer_stream_format_binary() {
+        let msg = ProducerMessage::StreamFormat {
+            stream_id: 42,
+            sample_rate: 22050,
+            channels: 2,
+            encoding: AudioEncoding::F32le,
+        };
+        let mut bytes = msg.to_bytes().unwrap();
+        assert_eq!(bytes[0], ProducerMessageType::StreamFormat as u8);
+        assert_eq!(bytes.len(), 5 + 15);
+
+        let mut connection = ProducerConnection::new(Cursor::new(bytes.clone()));
+        match connection.read_message().unwrap() {
+            ProducerMessage::StreamFormat {
+                stream_id,
+                sample_rate,
+                channels,
+                encoding,
+            } => {
+                assert_eq!(stream_id, 42);
+                assert_eq!(sample_rate, 22050);
+                assert_eq!(channels, 2);
+                assert_eq!(encoding, AudioEncoding::F32le);
+            }
+            other => panic!("Expected StreamFormat message, got {:?}", other),
+        }
+
+        // Unknown encodings are rejected rather than played as noise
+        *bytes.last_mut().unwrap() = 9;
+        let mut connection = ProducerConnection::new(Cursor::new(bytes));
+        assert!(matches!(
+            connection.read_message(),
+            Err(ProtocolError::InvalidAudioEncoding(9))
+        ));
+        assert_eq!("opus".parse::<AudioEncoding>(), Ok(AudioEncoding::Opus));
+    }
+
     #[test]
     fn test_producer_playback_complete_binary() {
         let msg = ProducerMessage::PlaybackComplete {
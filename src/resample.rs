@@ -0,0 +1,308 @@
+//! Streaming sample-rate conversion for capture and playback.
+//!
+//! `Resampler` converts a continuous mono f32 stream from one rate to another
+//! by interpolating with a tabulated kernel: a Blackman-windowed sinc, low-pass
+//! filtered at the lower of the two Nyquist frequencies, or a linear ramp for
+//! `ResampleQuality::Fast`. State carries across `process` calls, so audio can
+//! be fed in callback- or chunk-sized pieces without clicks at the boundaries.
+//! Buffers only grow to the largest block seen, so steady-state processing
+//! does not allocate.
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// Kernel phases tabulated between two neighbouring input samples
+const PHASES: usize = 256;
+
+/// Trade-off between resampling quality and CPU
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Linear interpolation; no anti-aliasing filter, lowest CPU
+    Fast,
+    /// Windowed sinc with 8 zero crossings per side
+    #[default]
+    Balanced,
+    /// Windowed sinc with 32 zero crossings per side
+    High,
+}
+
+impl ResampleQuality {
+    /// Zero crossings of the kernel on each side of its centre
+    fn zero_crossings(&self) -> usize {
+        match self {
+            ResampleQuality::Fast => 1,
+            ResampleQuality::Balanced => 8,
+            ResampleQuality::High => 32,
+        }
+    }
+}
+
+impl FromStr for ResampleQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fast" => Ok(ResampleQuality::Fast),
+            "balanced" => Ok(ResampleQuality::Balanced),
+            "high" => Ok(ResampleQuality::High),
+            other => Err(format!(
+                "unknown resample quality '{}' (expected fast, balanced or high)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResampleQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResampleQuality::Fast => write!(f, "fast"),
+            ResampleQuality::Balanced => write!(f, "balanced"),
+            ResampleQuality::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Stateful mono sample-rate converter
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    /// Input samples advanced per output sample, as a whole part plus a
+    /// fraction of `output_rate`, so the position never drifts
+    step: usize,
+    step_frac: u64,
+    output_rate: u64,
+    /// Position of the next output sample in `history`: input index `pos`
+    /// plus `frac / output_rate`
+    pos: usize,
+    frac: u64,
+    /// Kernel half-width in input samples
+    radius: usize,
+    /// `PHASES + 1` rows of `2 * radius` taps, each summing to 1
+    table: Vec<f32>,
+    /// Input not yet fully consumed, starting `radius - 1` samples before
+    /// the next output's position
+    history: Vec<f32>,
+    passthrough: bool,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, quality: ResampleQuality) -> Self {
+        // Low-pass at the output's Nyquist frequency when downsampling
+        let cutoff = (output_rate as f64 / input_rate as f64).min(1.0);
+        let radius = match quality {
+            ResampleQuality::Fast => 1,
+            _ => (quality.zero_crossings() as f64 / cutoff).ceil() as usize,
+        };
+
+        let taps = 2 * radius;
+        let mut table = Vec::with_capacity((PHASES + 1) * taps);
+        for phase in 0..=PHASES {
+            let frac = phase as f64 / PHASES as f64;
+            let row: Vec<f64> = (0..taps)
+                .map(|k| {
+                    // Distance from the output position to input tap `k`
+                    let d = (k as f64 + 1.0 - radius as f64) - frac;
+                    match quality {
+                        ResampleQuality::Fast => (1.0 - d.abs()).max(0.0),
+                        _ => sinc(cutoff * d) * blackman(d / radius as f64),
+                    }
+                })
+                .collect();
+            let sum: f64 = row.iter().sum();
+            table.extend(row.iter().map(|&h| (h / sum) as f32));
+        }
+
+        let mut resampler = Self {
+            step: (input_rate / output_rate) as usize,
+            step_frac: (input_rate % output_rate) as u64,
+            output_rate: output_rate as u64,
+            pos: 0,
+            frac: 0,
+            radius,
+            table,
+            history: Vec::new(),
+            passthrough: input_rate == output_rate,
+        };
+        resampler.reset();
+        resampler
+    }
+
+    /// True when input and output rates match and samples are copied as-is
+    pub fn is_passthrough(&self) -> bool {
+        self.passthrough
+    }
+
+    /// Forget buffered input, e.g. before an unrelated stream starts
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.history.resize(self.radius - 1, 0.0);
+        self.pos = self.radius - 1;
+        self.frac = 0;
+    }
+
+    /// Resample `input` and append the result to `output`. Output lags the
+    /// input by the kernel radius, which is held back until more input arrives.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.passthrough {
+            output.extend_from_slice(input);
+            return;
+        }
+
+        self.history.extend_from_slice(input);
+        let taps = 2 * self.radius;
+        loop {
+            let start = self.pos + 1 - self.radius;
+            if start + taps > self.history.len() {
+                break;
+            }
+            let phase =
+                ((self.frac * PHASES as u64 + self.output_rate / 2) / self.output_rate) as usize;
+            let kernel = &self.table[phase * taps..(phase + 1) * taps];
+            let window = &self.history[start..start + taps];
+            output.push(kernel.iter().zip(window).map(|(h, x)| h * x).sum());
+
+            self.pos += self.step;
+            self.frac += self.step_frac;
+            if self.frac >= self.output_rate {
+                self.frac -= self.output_rate;
+                self.pos += 1;
+            }
+        }
+
+        let consumed = (self.pos + 1 - self.radius).min(self.history.len());
+        self.history.drain(..consumed);
+        self.pos -= consumed;
+    }
+
+    /// Append the output still held back for lack of lookahead, as if the
+    /// input ended in silence, and get ready for a new stream
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        if !self.passthrough {
+            let silence = vec![0.0; self.radius];
+            self.process(&silence, output);
+        }
+        self.reset();
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Blackman window over `x` in -1..=1
+fn blackman(x: f64) -> f64 {
+    if x.abs() >= 1.0 {
+        return 0.0;
+    }
+    let t = PI * (x + 1.0);
+    0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(freq: f64, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| (0.5 * (2.0 * PI * freq * n as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    fn resample_in_blocks(resampler: &mut Resampler, input: &[f32], block: usize) -> Vec<f32> {
+        let mut output = Vec::new();
+        for piece in input.chunks(block) {
+            resampler.process(piece, &mut output);
+        }
+        output
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let input = tone(440.0, 16000, 1000);
+        let mut resampler = Resampler::new(16000, 16000, ResampleQuality::High);
+        assert!(resampler.is_passthrough());
+        assert_eq!(resample_in_blocks(&mut resampler, &input, 333), input);
+    }
+
+    #[test]
+    fn test_tone_survives_conversion() {
+        for (input_rate, output_rate) in [(48000, 16000), (44100, 16000), (16000, 48000)] {
+            for (quality, tolerance) in [
+                (ResampleQuality::Fast, 0.05),
+                (ResampleQuality::Balanced, 0.01),
+                (ResampleQuality::High, 0.005),
+            ] {
+                let input = tone(1000.0, input_rate, input_rate as usize);
+                let mut resampler = Resampler::new(input_rate, output_rate, quality);
+                let output = resample_in_blocks(&mut resampler, &input, 441);
+
+                // Everything but the kernel's latency comes out
+                let expected_len = output_rate as usize;
+                assert!(output.len() <= expected_len);
+                assert!(output.len() + 200 > expected_len, "{}", output.len());
+
+                let ideal = tone(1000.0, output_rate, output.len());
+                let error = output[100..]
+                    .iter()
+                    .zip(&ideal[100..])
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0.0f32, f32::max);
+                assert!(
+                    error < tolerance,
+                    "{}Hz -> {}Hz ({}): error {}",
+                    input_rate,
+                    output_rate,
+                    quality,
+                    error
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_size_does_not_matter() {
+        let input = tone(300.0, 44100, 20000);
+        let mut whole = Resampler::new(44100, 16000, ResampleQuality::Balanced);
+        let mut pieces = whole.clone();
+        assert_eq!(
+            resample_in_blocks(&mut whole, &input, input.len()),
+            resample_in_blocks(&mut pieces, &input, 97)
+        );
+    }
+
+    #[test]
+    fn test_flush_emits_the_tail() {
+        let input = tone(300.0, 24000, 24000);
+        let mut resampler = Resampler::new(24000, 48000, ResampleQuality::High);
+        let mut output = resample_in_blocks(&mut resampler, &input, 480);
+        assert!(output.len() < 48000);
+        resampler.flush(&mut output);
+        assert_eq!(output.len(), 48000);
+
+        // After a flush the next stream starts from silence again
+        let mut fresh = Resampler::new(24000, 48000, ResampleQuality::High);
+        assert_eq!(
+            resample_in_blocks(&mut resampler, &input, 480),
+            resample_in_blocks(&mut fresh, &input, 480)
+        );
+    }
+
+    #[test]
+    fn test_downsampling_rejects_aliases() {
+        // 10kHz is above the 8kHz output Nyquist and would alias to 6kHz
+        let input = tone(10000.0, 48000, 48000);
+        let mut resampler = Resampler::new(48000, 16000, ResampleQuality::High);
+        let output = resample_in_blocks(&mut resampler, &input, 480);
+        let rms =
+            (output[100..].iter().map(|x| x * x).sum::<f32>() / (output.len() - 100) as f32).sqrt();
+        assert!(rms < 0.01, "alias rms {}", rms);
+
+        assert!("fast".parse::<ResampleQuality>() == Ok(ResampleQuality::Fast));
+        assert!("best".parse::<ResampleQuality>().is_err());
+    }
+}
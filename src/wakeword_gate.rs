@@ -0,0 +1,419 @@
+//! Wake word firing decisions.
+//!
+//! Turns raw per-model predictions into accepted detections by applying each
+//! model's threshold, debounce and refractory period. Shared by the live
+//! detection thread and the offline `ww_eval` tool so both make exactly the
+//! same decisions. Time is passed in by the caller as a `Duration` on any
+//! monotonic clock (wall clock live, audio time offline).
+//!
+//! When several capture channels are scored, `ChannelPolicy` first folds each
+//! model's per-channel scores into one prediction for the gate.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Default minimum time between two accepted detections.
+pub const WAKEWORD_DEBOUNCE_MS: u64 = 3000;
+
+/// Detection operating point for one wake word model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionSettings {
+    /// Confidence at or above which the model fires.
+    pub threshold: f32,
+    /// Minimum time between two detections of this model.
+    pub debounce_ms: u64,
+    /// After this model fires, no model may fire for this long.
+    pub refractory_ms: u64,
+}
+
+impl Default for DetectionSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            debounce_ms: WAKEWORD_DEBOUNCE_MS,
+            refractory_ms: WAKEWORD_DEBOUNCE_MS,
+        }
+    }
+}
+
+/// Per-model changes to the default detection settings. Unset fields keep
+/// the model's default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DetectionOverride {
+    pub threshold: Option<f32>,
+    pub debounce_ms: Option<u64>,
+    pub refractory_ms: Option<u64>,
+}
+
+impl DetectionOverride {
+    /// Parse a per-model override such as
+    /// `hey_mycroft:threshold=0.6,debounce_ms=2000,refractory_ms=1000`.
+    pub fn parse(spec: &str) -> Result<(String, DetectionOverride), String> {
+        let (model, settings) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("expected MODEL:key=value[,key=value...], got '{}'", spec))?;
+        if model.is_empty() {
+            return Err(format!("missing model name in '{}'", spec));
+        }
+
+        let mut result = DetectionOverride::default();
+        for pair in settings.split(',').filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", pair))?;
+            let invalid = |e: &dyn std::fmt::Display| format!("invalid {} '{}': {}", key, value, e);
+            match key.trim() {
+                "threshold" => {
+                    result.threshold = Some(value.trim().parse().map_err(|e| invalid(&e))?)
+                }
+                "debounce_ms" => {
+                    result.debounce_ms = Some(value.trim().parse().map_err(|e| invalid(&e))?)
+                }
+                "refractory_ms" => {
+                    result.refractory_ms = Some(value.trim().parse().map_err(|e| invalid(&e))?)
+                }
+                other => {
+                    return Err(format!(
+                        "unknown setting '{}' (expected threshold, debounce_ms or refractory_ms)",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok((model.to_string(), result))
+    }
+
+    /// `base` with the fields set in this override replaced
+    pub fn apply(&self, base: &DetectionSettings) -> DetectionSettings {
+        DetectionSettings {
+            threshold: self.threshold.unwrap_or(base.threshold),
+            debounce_ms: self.debounce_ms.unwrap_or(base.debounce_ms),
+            refractory_ms: self.refractory_ms.unwrap_or(base.refractory_ms),
+        }
+    }
+}
+
+/// How many scored channels must agree before a wake word fires.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelPolicy {
+    /// Any single channel crossing the threshold fires
+    #[default]
+    Any,
+    /// Every channel must cross the threshold
+    All,
+    /// More than half of the channels must cross the threshold
+    Majority,
+}
+
+impl ChannelPolicy {
+    /// Number of channels out of `channels` that must cross the threshold
+    pub fn required(&self, channels: usize) -> usize {
+        match self {
+            ChannelPolicy::Any => channels.min(1),
+            ChannelPolicy::All => channels,
+            ChannelPolicy::Majority => channels / 2 + 1,
+        }
+    }
+
+    /// Fold per-channel predictions into one prediction per model: the
+    /// highest score reached by at least `required` channels. The result
+    /// crosses a threshold exactly when enough channels do, so it can go
+    /// straight into `WakewordGate::select`.
+    pub fn combine(&self, per_channel: &[HashMap<String, f32>]) -> HashMap<String, f32> {
+        let required = self.required(per_channel.len()).max(1);
+        let Some(first) = per_channel.first() else {
+            return HashMap::new();
+        };
+        first
+            .keys()
+            .map(|model| {
+                let mut scores: Vec<f32> = per_channel
+                    .iter()
+                    .map(|p| p.get(model).copied().unwrap_or(0.0))
+                    .collect();
+                scores.sort_by(|a, b| b.total_cmp(a));
+                (model.clone(), scores[required - 1])
+            })
+            .collect()
+    }
+
+    /// Index of the channel with the highest score for `model`
+    pub fn strongest_channel(per_channel: &[HashMap<String, f32>], model: &str) -> usize {
+        per_channel
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                let a = a.get(model).copied().unwrap_or(0.0);
+                let b = b.get(model).copied().unwrap_or(0.0);
+                a.total_cmp(&b)
+            })
+            .map_or(0, |(index, _)| index)
+    }
+}
+
+impl FromStr for ChannelPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(ChannelPolicy::Any),
+            "all" => Ok(ChannelPolicy::All),
+            "majority" => Ok(ChannelPolicy::Majority),
+            other => Err(format!(
+                "unknown channel policy '{}' (expected any, all or majority)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ChannelPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelPolicy::Any => write!(f, "any"),
+            ChannelPolicy::All => write!(f, "all"),
+            ChannelPolicy::Majority => write!(f, "majority"),
+        }
+    }
+}
+
+/// An accepted detection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakewordFire {
+    pub model: String,
+    pub confidence: f32,
+    /// The threshold of `model` that was crossed.
+    pub threshold: f32,
+}
+
+/// Stateful gate applying per-model detection settings to predictions.
+#[derive(Debug, Clone)]
+pub struct WakewordGate {
+    defaults: DetectionSettings,
+    per_model: HashMap<String, DetectionSettings>,
+    last_fire: HashMap<String, Duration>,
+    refractory_until: Option<Duration>,
+}
+
+impl WakewordGate {
+    pub fn new(defaults: DetectionSettings, per_model: HashMap<String, DetectionSettings>) -> Self {
+        Self {
+            defaults,
+            per_model,
+            last_fire: HashMap::new(),
+            refractory_until: None,
+        }
+    }
+
+    /// Settings in effect for `model`.
+    pub fn settings(&self, model: &str) -> &DetectionSettings {
+        self.per_model.get(model).unwrap_or(&self.defaults)
+    }
+
+    /// Forget all previous detections.
+    pub fn reset(&mut self) {
+        self.last_fire.clear();
+        self.refractory_until = None;
+    }
+
+    /// Decide which wake word (if any) fires for one hop of predictions at time `now`.
+    ///
+    /// Returns the highest-scoring model that crossed its own threshold and is
+    /// not debounced or inside another detection's refractory period, plus the
+    /// peak confidence across all models, which is reported even when nothing
+    /// fires so callers can track near-misses.
+    pub fn select(
+        &mut self,
+        predictions: &HashMap<String, f32>,
+        now: Duration,
+    ) -> (Option<WakewordFire>, f32) {
+        let max_conf = predictions.values().copied().fold(0.0f32, f32::max);
+        let in_refractory = self.refractory_until.is_some_and(|until| now < until);
+
+        let mut best: Option<WakewordFire> = None;
+        for (model, &confidence) in predictions {
+            let settings = self.settings(model);
+            if confidence < settings.threshold {
+                continue;
+            }
+
+            let since_last = self
+                .last_fire
+                .get(model)
+                .map(|&last| now.saturating_sub(last));
+            let debounced =
+                since_last.is_some_and(|since| since.as_millis() < settings.debounce_ms as u128);
+            if debounced || in_refractory {
+                log::debug!(
+                    "🔇 [Detection] Wake word '{}' {} (confidence {:.6})",
+                    model,
+                    if debounced {
+                        "debounced"
+                    } else {
+                        "in refractory period"
+                    },
+                    confidence
+                );
+                continue;
+            }
+
+            if best.as_ref().is_none_or(|b| confidence > b.confidence) {
+                best = Some(WakewordFire {
+                    model: model.clone(),
+                    confidence,
+                    threshold: settings.threshold,
+                });
+            }
+        }
+
+        if let Some(ref fire) = best {
+            let refractory = Duration::from_millis(self.settings(&fire.model).refractory_ms);
+            self.last_fire.insert(fire.model.clone(), now);
+            self.refractory_until = Some(now + refractory);
+        }
+
+        (best, max_conf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predictions(scores: &[(&str, f32)]) -> HashMap<String, f32> {
+        scores.iter().map(|(m, c)| (m.to_string(), *c)).collect()
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_per_model_threshold() {
+        let mut per_model = HashMap::new();
+        per_model.insert(
+            "alexa".to_string(),
+            DetectionSettings {
+                threshold: 0.8,
+                ..Default::default()
+            },
+        );
+        let mut gate = WakewordGate::new(DetectionSettings::default(), per_model);
+
+        let (fire, peak) =
+            gate.select(&predictions(&[("alexa", 0.7), ("hey_mycroft", 0.4)]), ms(0));
+        assert_eq!(fire, None);
+        assert_eq!(peak, 0.7);
+
+        let (fire, _) = gate.select(
+            &predictions(&[("alexa", 0.7), ("hey_mycroft", 0.6)]),
+            ms(80),
+        );
+        let fire = fire.unwrap();
+        assert_eq!(fire.model, "hey_mycroft");
+        assert_eq!(fire.threshold, 0.5);
+    }
+
+    #[test]
+    fn test_debounce_and_refractory() {
+        let defaults = DetectionSettings {
+            threshold: 0.5,
+            debounce_ms: 1000,
+            refractory_ms: 200,
+        };
+        let mut gate = WakewordGate::new(defaults, HashMap::new());
+
+        assert!(gate.select(&predictions(&[("a", 0.9)]), ms(0)).0.is_some());
+        // Another model is locked out during the refractory period...
+        assert!(gate
+            .select(&predictions(&[("b", 0.9)]), ms(100))
+            .0
+            .is_none());
+        // ...but may fire after it, while the first model is still debounced.
+        assert_eq!(
+            gate.select(&predictions(&[("a", 0.95), ("b", 0.9)]), ms(300))
+                .0
+                .unwrap()
+                .model,
+            "b"
+        );
+        assert!(gate
+            .select(&predictions(&[("a", 0.9)]), ms(900))
+            .0
+            .is_none());
+        assert!(gate
+            .select(&predictions(&[("a", 0.9)]), ms(1000))
+            .0
+            .is_some());
+
+        gate.reset();
+        assert!(gate
+            .select(&predictions(&[("a", 0.9)]), ms(1001))
+            .0
+            .is_some());
+    }
+
+    #[test]
+    fn test_channel_policy() {
+        let per_channel = [
+            predictions(&[("a", 0.9), ("b", 0.1)]),
+            predictions(&[("a", 0.6), ("b", 0.2)]),
+            predictions(&[("a", 0.3), ("b", 0.7)]),
+        ];
+
+        let any = ChannelPolicy::Any.combine(&per_channel);
+        assert_eq!(any["a"], 0.9);
+        assert_eq!(any["b"], 0.7);
+        let majority = ChannelPolicy::Majority.combine(&per_channel);
+        assert_eq!(majority["a"], 0.6);
+        assert_eq!(majority["b"], 0.2);
+        let all = ChannelPolicy::All.combine(&per_channel);
+        assert_eq!(all["a"], 0.3);
+        assert_eq!(all["b"], 0.1);
+
+        assert_eq!(ChannelPolicy::strongest_channel(&per_channel, "a"), 0);
+        assert_eq!(ChannelPolicy::strongest_channel(&per_channel, "b"), 2);
+
+        // A single channel is scored as-is under every policy
+        for policy in [
+            ChannelPolicy::Any,
+            ChannelPolicy::All,
+            ChannelPolicy::Majority,
+        ] {
+            assert_eq!(policy.combine(&per_channel[..1]), per_channel[0]);
+            assert_eq!(policy.to_string().parse::<ChannelPolicy>(), Ok(policy));
+        }
+        assert!("most".parse::<ChannelPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_parse_override() {
+        let (model, o) =
+            DetectionOverride::parse("hey_mycroft:threshold=0.6,refractory_ms=500").unwrap();
+        assert_eq!(model, "hey_mycroft");
+        assert_eq!(o.threshold, Some(0.6));
+        assert_eq!(o.debounce_ms, None);
+        assert_eq!(o.refractory_ms, Some(500));
+
+        let base = DetectionSettings {
+            threshold: 0.7,
+            debounce_ms: 100,
+            refractory_ms: 100,
+        };
+        let applied = o.apply(&base);
+        assert_eq!(applied.threshold, 0.6);
+        assert_eq!(applied.debounce_ms, 100);
+        assert_eq!(applied.refractory_ms, 500);
+
+        for bad in [
+            "hey_mycroft",
+            ":threshold=0.5",
+            "hey_mycroft:gain=2",
+            "hey_mycroft:threshold=x",
+        ] {
+            assert!(DetectionOverride::parse(bad).is_err());
+        }
+    }
+}
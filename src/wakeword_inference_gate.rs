@@ -0,0 +1,164 @@
+//! Energy-gated wake word inference.
+//!
+//! Decides per 1280-sample hop whether the embedding and wake word models
+//! need to run. The gate opens on the first loud hop (or while the VAD is in
+//! speech) and only closes after `hold_ms` of continuous quiet, so short
+//! pauses never stop inference. While it is closed the melspectrogram is
+//! still computed, and `wakeword_model::Model` backfills the skipped
+//! embeddings when it reopens, so scores after reopening are identical to
+//! ungated inference. Shared by the live detection thread and `ww_eval`.
+
+/// Settings for the inference gate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InferenceGateConfig {
+    /// Hops at or above this RMS level (dBFS) open the gate.
+    pub open_dbfs: f32,
+    /// Continuous quiet required before the gate closes.
+    pub hold_ms: u64,
+}
+
+impl Default for InferenceGateConfig {
+    fn default() -> Self {
+        Self {
+            open_dbfs: -55.0,
+            hold_ms: 2000,
+        }
+    }
+}
+
+/// How many hops ran full inference and how many were skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InferenceStats {
+    pub run: u64,
+    pub skipped: u64,
+}
+
+impl InferenceStats {
+    /// Fraction of hops whose inference was skipped.
+    pub fn skipped_ratio(&self) -> f64 {
+        let total = self.run + self.skipped;
+        if total == 0 {
+            0.0
+        } else {
+            self.skipped as f64 / total as f64
+        }
+    }
+}
+
+/// Hysteresis gate deciding whether a hop needs full inference.
+#[derive(Debug, Clone)]
+pub struct InferenceGate {
+    open_dbfs: f32,
+    hold_samples: u64,
+    /// Samples since the last loud or speech hop
+    quiet_samples: u64,
+    stats: InferenceStats,
+}
+
+impl InferenceGate {
+    /// A new gate starts open so the models warm up on real audio.
+    pub fn new(config: &InferenceGateConfig) -> Self {
+        Self {
+            open_dbfs: config.open_dbfs,
+            hold_samples: config.hold_ms * 16,
+            quiet_samples: 0,
+            stats: InferenceStats::default(),
+        }
+    }
+
+    /// Decide whether to run inference on `samples`. `speech` is the latest
+    /// VAD decision and keeps the gate open like a loud hop does.
+    pub fn should_run(&mut self, samples: &[i16], speech: bool) -> bool {
+        if speech || rms_dbfs(samples) >= self.open_dbfs {
+            self.quiet_samples = 0;
+        } else {
+            self.quiet_samples += samples.len() as u64;
+        }
+
+        let run = self.is_open();
+        if run {
+            self.stats.run += 1;
+        } else {
+            self.stats.skipped += 1;
+        }
+        run
+    }
+
+    /// Whether the last hop ran inference.
+    pub fn is_open(&self) -> bool {
+        self.quiet_samples <= self.hold_samples
+    }
+
+    pub fn stats(&self) -> InferenceStats {
+        self.stats
+    }
+
+    /// Reopen the gate, e.g. after the model was replaced. Counters are kept.
+    pub fn reset(&mut self) {
+        self.quiet_samples = 0;
+    }
+}
+
+/// RMS level of `samples` relative to full scale, or `-inf` for silence.
+pub fn rms_dbfs(samples: &[i16]) -> f32 {
+    if samples.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let sum_squares: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let rms = (sum_squares / samples.len() as f64).sqrt() / 32768.0;
+    20.0 * rms.log10() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(amplitude: i16) -> Vec<i16> {
+        (0..1280)
+            .map(|i| if i % 2 == 0 { amplitude } else { -amplitude })
+            .collect()
+    }
+
+    #[test]
+    fn test_rms_dbfs() {
+        assert_eq!(rms_dbfs(&hop(0)), f32::NEG_INFINITY);
+        assert!(rms_dbfs(&hop(i16::MAX)).abs() < 0.01);
+        // 32768 / 10 is 20 dB below full scale
+        assert!((rms_dbfs(&hop(3277)) + 20.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_gate_hysteresis() {
+        // Closes after more than 3 quiet hops (240ms)
+        let mut gate = InferenceGate::new(&InferenceGateConfig {
+            open_dbfs: -40.0,
+            hold_ms: 240,
+        });
+        let quiet = hop(10);
+        let loud = hop(3000);
+
+        let runs: Vec<bool> = (0..5).map(|_| gate.should_run(&quiet, false)).collect();
+        assert_eq!(runs, vec![true, true, true, false, false]);
+
+        // A single loud hop reopens immediately and restarts the hold
+        assert!(gate.should_run(&loud, false));
+        assert!(gate.should_run(&quiet, false));
+        assert!(gate.should_run(&quiet, false));
+        assert!(gate.should_run(&quiet, false));
+        assert!(!gate.should_run(&quiet, false));
+
+        // Speech keeps it open even when quiet
+        assert!(gate.should_run(&quiet, true));
+
+        assert_eq!(
+            gate.stats(),
+            InferenceStats {
+                run: 8,
+                skipped: 3
+            }
+        );
+
+        gate.reset();
+        assert!(gate.is_open());
+    }
+}
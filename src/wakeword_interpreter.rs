@@ -0,0 +1,70 @@
+//! TensorFlow Lite interpreters that own their model.
+//!
+//! `tflitec::interpreter::Interpreter` borrows the `Model` it was built from,
+//! so storing one in a long-lived struct needs the model to outlive it.
+//! `OwnedInterpreter` keeps the model on the heap next to the interpreter and
+//! frees both together, so wake word models can be dropped and reloaded
+//! without leaking the model file each time.
+
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use tflitec::interpreter::{Interpreter, Options};
+use tflitec::model::Model as TfliteModel;
+
+/// An interpreter together with the model it borrows
+pub struct OwnedInterpreter {
+    interpreter: ManuallyDrop<Interpreter<'static>>,
+    /// Heap allocation borrowed by `interpreter`; freed after it in `drop`
+    model: *mut TfliteModel<'static>,
+}
+
+// The model is never touched except through the interpreter, which tflitec
+// already allows to move between threads.
+unsafe impl Send for OwnedInterpreter {}
+
+impl OwnedInterpreter {
+    /// Load the model at `path` and build an interpreter for it. Tensors are
+    /// not allocated yet, so inputs can still be resized.
+    pub fn new(path: &str, options: Options) -> tflitec::Result<Self> {
+        let model = Box::into_raw(Box::new(TfliteModel::new(path)?));
+
+        // SAFETY: `model` stays at the same address until `drop`, which frees
+        // it only after the interpreter borrowing it
+        match Interpreter::new(unsafe { &*model }, Some(options)) {
+            Ok(interpreter) => Ok(Self {
+                interpreter: ManuallyDrop::new(interpreter),
+                model,
+            }),
+            Err(e) => {
+                // SAFETY: nothing borrows the model anymore
+                drop(unsafe { Box::from_raw(model) });
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Deref for OwnedInterpreter {
+    type Target = Interpreter<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.interpreter
+    }
+}
+
+impl DerefMut for OwnedInterpreter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.interpreter
+    }
+}
+
+impl Drop for OwnedInterpreter {
+    fn drop(&mut self) {
+        // SAFETY: the interpreter goes first because it borrows the model, and
+        // neither is used again
+        unsafe {
+            ManuallyDrop::drop(&mut self.interpreter);
+            drop(Box::from_raw(self.model));
+        }
+    }
+}
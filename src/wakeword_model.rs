@@ -4,11 +4,12 @@
 //! including proper prediction buffer management and simplified prediction interface.
 
 use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 use tflitec::interpreter::Interpreter;
-use tflitec::model::Model as TfliteModel;
 
 use crate::wakeword_error::{OpenWakeWordError, Result};
-use crate::wakeword_models::{get_model_class_mappings, FEATURE_MODELS, MODELS};
+use crate::wakeword_interpreter::OwnedInterpreter;
+use crate::wakeword_models::{ModelKind, ModelRegistry};
 use crate::wakeword_utils::AudioFeatures;
 
 /// Type alias for prediction results
@@ -17,7 +18,7 @@ pub type PredictionResult = HashMap<String, f32>;
 /// Main model struct that holds all wake word models and shared preprocessor
 pub struct Model {
     // Model storage
-    models: HashMap<String, Interpreter<'static>>,
+    models: HashMap<String, OwnedInterpreter>,
     model_inputs: HashMap<String, usize>,
 
     // Class mappings for multi-class models
@@ -34,7 +35,7 @@ impl Model {
     /// Create a new Model instance
     ///
     /// # Arguments
-    /// * `wakeword_models` - List of model names or paths to load
+    /// * `wakeword_models` - Manifest names, manifest paths or `.tflite` paths to load
     /// * `class_mapping_dicts` - Optional class mappings for multi-class models
     ///
     /// # Returns
@@ -49,9 +50,9 @@ impl Model {
     /// Create a new Model instance with custom model directory path
     ///
     /// # Arguments
-    /// * `wakeword_models` - List of model names or paths to load
+    /// * `wakeword_models` - Manifest names, manifest paths or `.tflite` paths to load
     /// * `class_mapping_dicts` - Optional class mappings for multi-class models
-    /// * `model_dir` - Directory containing the model files
+    /// * `model_dir` - Directory containing the model files and their manifests
     ///
     /// # Returns
     /// * `Result<Model>` - The created model instance
@@ -60,80 +61,50 @@ impl Model {
         class_mapping_dicts: Vec<HashMap<String, String>>,
         model_dir: &str,
     ) -> Result<Self> {
-        let model_paths = MODELS;
-        let mut model_names = Vec::new();
-        let mut resolved_paths = Vec::new();
+        Self::new_with_feature_snapshot(wakeword_models, class_mapping_dicts, model_dir, None)
+    }
+
+    /// Create a new Model instance like `new_with_model_path`, restoring the
+    /// preprocessor from the snapshot file at `feature_snapshot` instead of
+    /// running the warmup inference. A missing or stale snapshot is replaced
+    /// after warming up, so only the first load pays for it.
+    pub fn new_with_feature_snapshot(
+        wakeword_models: Vec<String>,
+        class_mapping_dicts: Vec<HashMap<String, String>>,
+        model_dir: &str,
+        feature_snapshot: Option<&Path>,
+    ) -> Result<Self> {
+        let registry = ModelRegistry::discover(model_dir)?;
+        let mut manifests = Vec::new();
 
-        // Resolve model names to paths
+        // Resolve model names to manifests
         for model in wakeword_models {
-            if std::path::Path::new(&model).exists() {
-                // Direct path
-                resolved_paths.push(model.clone());
-                model_names.push(
-                    std::path::Path::new(&model)
-                        .file_stem()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string(),
-                );
-            } else {
-                // Look up by name and combine with model_dir
-                if let Some((_, relative_path)) =
-                    model_paths.iter().find(|(name, _)| *name == model)
-                {
-                    // Extract filename from the relative path and combine with model_dir
-                    let filename = std::path::Path::new(relative_path)
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .ok_or_else(|| {
-                            OpenWakeWordError::ModelLoadError(format!(
-                                "Invalid model path: {}",
-                                relative_path
-                            ))
-                        })?;
-                    let full_path = std::path::Path::new(model_dir).join(filename);
-                    resolved_paths.push(full_path.to_string_lossy().to_string());
-                    model_names.push(model);
-                } else {
-                    return Err(OpenWakeWordError::ModelLoadError(format!(
-                        "Model not found: {}",
-                        model
-                    )));
-                }
-            }
+            manifests.push(registry.resolve_wakeword(&model)?);
         }
+        let model_names: Vec<String> = manifests.iter().map(|m| m.name.clone()).collect();
 
         // Load models
         let mut models = HashMap::new();
         let mut model_inputs = HashMap::new();
         let mut model_outputs = HashMap::new();
 
-        for (model_name, model_path) in model_names.iter().zip(resolved_paths.iter()) {
+        for manifest in &manifests {
+            let model_name = &manifest.name;
+            let model_path = manifest.path.to_string_lossy().to_string();
             log::debug!("Loading model: {} from {}", model_name, model_path);
 
             // === MODEL FILE VALIDATION ===
-            let model_data = std::fs::read(model_path).map_err(|e| {
-                OpenWakeWordError::ModelLoadError(format!(
-                    "Failed to read model file {}: {}",
-                    model_path, e
-                ))
-            })?;
-
-            let file_size = model_data.len();
-            let hash = {
-                use std::collections::hash_map::DefaultHasher;
-                use std::hash::{Hash, Hasher};
-                let mut hasher = DefaultHasher::new();
-                model_data.hash(&mut hasher);
-                hasher.finish()
-            };
+            let model_data = manifest.read_verified()?;
 
             log::info!(
-                "🔍 MODEL_FILE_CHECK: {} - size={} bytes, hash={:x}",
+                "🔍 MODEL_FILE_CHECK: {} - size={} bytes, sha256 {}",
                 model_name,
-                file_size,
-                hash
+                model_data.len(),
+                if manifest.sha256.is_some() {
+                    "verified"
+                } else {
+                    "not in manifest"
+                }
             );
 
             // === RUNTIME PLATFORM DIAGNOSTICS ===
@@ -164,24 +135,16 @@ impl Model {
                 log::info!("🔍 LINUX_DEBUG: Model {} loading on Linux", model_name);
             }
 
-            // Load TFLite model (same pattern as utils.rs)
-            let tflite_model = Box::leak(Box::new(TfliteModel::new(model_path).map_err(|e| {
-                OpenWakeWordError::ModelLoadError(format!(
-                    "Failed to load model {}: {}",
-                    model_name, e
-                ))
-            })?));
-
             // Match Python's single-threaded configuration for consistency
             let options = tflitec::interpreter::Options {
                 thread_count: 1,          // Match Python's default (ncpu=1)
                 is_xnnpack_enabled: true, // Keep XNNPACK for performance
             };
 
-            let interpreter = tflitec::interpreter::Interpreter::new(tflite_model, Some(options))
-                .map_err(|e| {
+            // Load TFLite model (same pattern as utils.rs)
+            let interpreter = OwnedInterpreter::new(&model_path, options).map_err(|e| {
                 OpenWakeWordError::ModelLoadError(format!(
-                    "Failed to create interpreter for {}: {}",
+                    "Failed to load model {}: {}",
                     model_name, e
                 ))
             })?;
@@ -230,6 +193,15 @@ impl Model {
                 .copied()
                 .unwrap_or(0) as usize;
 
+            if let Some(expected_frames) = manifest.input_frames {
+                if expected_frames != input_size {
+                    return Err(OpenWakeWordError::ModelLoadError(format!(
+                        "Model {} expects {} input frames but its manifest says {}",
+                        model_name, input_size, expected_frames
+                    )));
+                }
+            }
+
             models.insert(model_name.clone(), interpreter);
             model_inputs.insert(model_name.clone(), input_size);
             model_outputs.insert(model_name.clone(), output_size);
@@ -237,13 +209,12 @@ impl Model {
 
         // Set up class mappings
         let mut class_mapping = HashMap::new();
-        let default_mappings = get_model_class_mappings();
-
-        for (i, model_name) in model_names.iter().enumerate() {
+        for (i, manifest) in manifests.iter().enumerate() {
+            let model_name = &manifest.name;
             if i < class_mapping_dicts.len() {
                 class_mapping.insert(model_name.clone(), class_mapping_dicts[i].clone());
-            } else if let Some(default_mapping) = default_mappings.get(model_name) {
-                class_mapping.insert(model_name.clone(), default_mapping.clone());
+            } else if !manifest.class_mapping.is_empty() {
+                class_mapping.insert(model_name.clone(), manifest.class_mapping.clone());
             } else {
                 // Create default mapping
                 let output_size = model_outputs[model_name];
@@ -256,35 +227,19 @@ impl Model {
         }
 
         // Initialize shared preprocessor
-        let melspec_filename = FEATURE_MODELS
-            .iter()
-            .find(|(name, _)| *name == "melspectrogram")
-            .map(|(_, path)| {
-                std::path::Path::new(path)
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .unwrap_or("melspectrogram.tflite")
-            })
-            .unwrap_or("melspectrogram.tflite");
-        let melspec_path = std::path::Path::new(model_dir).join(melspec_filename);
-
-        let embedding_filename = FEATURE_MODELS
-            .iter()
-            .find(|(name, _)| *name == "embedding")
-            .map(|(_, path)| {
-                std::path::Path::new(path)
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .unwrap_or("embedding_model.tflite")
-            })
-            .unwrap_or("embedding_model.tflite");
-        let embedding_path = std::path::Path::new(model_dir).join(embedding_filename);
-
-        let preprocessor = AudioFeatures::new(
-            &melspec_path.to_string_lossy(),
-            &embedding_path.to_string_lossy(),
-            16000,
-        )?;
+        let melspec = registry.feature(ModelKind::Melspectrogram)?;
+        let embedding = registry.feature(ModelKind::Embedding)?;
+        melspec.read_verified()?;
+        embedding.read_verified()?;
+
+        let melspec_path = melspec.path.to_string_lossy();
+        let embedding_path = embedding.path.to_string_lossy();
+        let preprocessor = match feature_snapshot {
+            Some(path) => {
+                AudioFeatures::with_snapshot_file(&melspec_path, &embedding_path, 16000, path)?
+            }
+            None => AudioFeatures::new(&melspec_path, &embedding_path, 16000)?,
+        };
 
         // Initialize prediction buffers (deque with maxlen=30) for each wakeword
         // model so warmup/buffering apply uniformly.
@@ -319,7 +274,8 @@ impl Model {
         Self::new_with_model_path(wakeword_models, class_mapping_dicts, model_dir)
     }
 
-    /// Reset internal state
+    /// Reset internal state. The preprocessor returns to its reset snapshot
+    /// without running any inference.
     pub fn reset(&mut self) -> Result<()> {
         self.preprocessor.reset()?;
         for buffer in self.prediction_buffer.values_mut() {
@@ -328,6 +284,20 @@ impl Model {
         Ok(())
     }
 
+    /// Make the current feature state the one `reset` returns to, e.g. after
+    /// a stretch of real room tone, and save it to `snapshot_path` for later
+    /// starts. Embeddings skipped by the inference gate are computed first so
+    /// the state is complete.
+    pub fn capture_reset_state(&mut self, snapshot_path: Option<&Path>) -> Result<()> {
+        let max_input_frames = self.model_inputs.values().copied().max().unwrap_or(0);
+        self.preprocessor.backfill_embeddings(max_input_frames)?;
+        let snapshot = self.preprocessor.snapshot();
+        if let Some(path) = snapshot_path {
+            snapshot.save(path)?;
+        }
+        self.preprocessor.set_reset_state(snapshot)
+    }
+
     /// Predict on audio data (matches Python predict method)
     ///
     /// Processes all input audio through the preprocessor at once, then runs
@@ -342,6 +312,11 @@ impl Model {
     ) -> Result<PredictionResult> {
         log::debug!("🔍 Starting prediction with {} audio samples", x.len());
 
+        // Catch up on embeddings skipped while inference was gated, so every
+        // model sees the same feature frames as if it had never been skipped
+        let max_input_frames = self.model_inputs.values().copied().max().unwrap_or(0);
+        self.preprocessor.backfill_embeddings(max_input_frames)?;
+
         // Process ALL audio through preprocessor at once (matches Python)
         let n_prepared_samples = self.preprocessor.__call__(x)?;
         log::debug!(
@@ -467,6 +442,25 @@ impl Model {
         Ok(predictions)
     }
 
+    /// Skip inference on audio data while keeping the melspectrogram current
+    ///
+    /// Used by the inference gate during sustained silence: only the cheap
+    /// melspectrogram model runs, every model predicts 0.0, and the next
+    /// `predict` backfills the embeddings it needs from the buffered mel frames.
+    pub fn skip(&mut self, x: &[i16]) -> Result<PredictionResult> {
+        self.preprocessor.buffer_melspectrogram(x)?;
+
+        let mut predictions = HashMap::new();
+        for (model_name, buffer) in self.prediction_buffer.iter_mut() {
+            buffer.push_back(0.0);
+            if buffer.len() > 30 {
+                buffer.pop_front();
+            }
+            predictions.insert(model_name.clone(), 0.0);
+        }
+        Ok(predictions)
+    }
+
     /// Get parent model name from label (for multi-class models)
     pub fn get_parent_model_from_label(&self, label: &str) -> Option<&str> {
         for (model_name, mapping) in self.class_mapping.iter() {
@@ -477,6 +471,11 @@ impl Model {
         None
     }
 
+    /// Recent predictions for `model`, oldest first (up to 30 frames)
+    pub fn prediction_buffer(&self, model: &str) -> Option<&VecDeque<f32>> {
+        self.prediction_buffer.get(model)
+    }
+
     /// Get model input sizes (for debugging)
     pub fn get_model_inputs(&self) -> &HashMap<String, usize> {
         &self.model_inputs
@@ -561,3 +560,124 @@ impl Model {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/models");
+
+    /// Resident set size in kB, or `None` where `/proc` is unavailable
+    fn rss_kb() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        status.lines().find_map(|line| {
+            line.strip_prefix("VmRSS:")?
+                .trim()
+                .strip_suffix("kB")?
+                .trim()
+                .parse()
+                .ok()
+        })
+    }
+
+    #[test]
+    fn test_model_drop_releases_memory() {
+        let load = || {
+            Model::new_with_model_path(vec!["hey_mycroft".to_string()], vec![], MODEL_DIR)
+                .expect("bundled models load")
+        };
+
+        // The first load sets up TFLite's process-wide state
+        drop(load());
+        let Some(before) = rss_kb() else {
+            return;
+        };
+
+        for _ in 0..30 {
+            drop(load());
+        }
+
+        // Leaking the ~3MB of model files on every load would add ~100MB
+        let growth = rss_kb().unwrap().saturating_sub(before);
+        assert!(growth < 20 * 1024, "RSS grew by {}kB over 30 loads", growth);
+    }
+
+    #[test]
+    fn test_gated_replay_matches_ungated_scores() {
+        use crate::wakeword_inference_gate::{InferenceGate, InferenceGateConfig};
+
+        let clip = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/golden/synthetic_sweep.raw"
+        ))
+        .expect("golden clip");
+        let samples: Vec<i16> = clip
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        let load = || {
+            Model::new_with_model_path(vec!["hey_mycroft".to_string()], vec![], MODEL_DIR)
+                .expect("bundled models load")
+        };
+
+        let mut ungated = load();
+        let expected: Vec<f32> = samples
+            .chunks_exact(1280)
+            .map(|hop| ungated.predict(hop, None, 1.0).unwrap()["hey_mycroft"])
+            .collect();
+
+        // Closes on the clip's quiet noise and reopens on the voiced sweep
+        let mut gate = InferenceGate::new(&InferenceGateConfig {
+            open_dbfs: -45.0,
+            hold_ms: 0,
+        });
+        let mut gated = load();
+        let mut skipped = false;
+        let mut compared = 0;
+        for (i, (hop, &expected)) in samples.chunks_exact(1280).zip(&expected).enumerate() {
+            if !gate.should_run(hop, false) {
+                gated.skip(hop).unwrap();
+                skipped = true;
+                continue;
+            }
+            let score = gated.predict(hop, None, 1.0).unwrap()["hey_mycroft"];
+            if skipped {
+                assert_eq!(score, expected, "hop {}", i);
+                compared += 1;
+            }
+        }
+        assert!(compared > 0, "the gate never reopened after skipping");
+    }
+
+    #[test]
+    fn test_feature_snapshot_file() {
+        let path = std::env::temp_dir().join(format!("ww_features_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let load = || {
+            Model::new_with_feature_snapshot(
+                vec!["hey_mycroft".to_string()],
+                vec![],
+                MODEL_DIR,
+                Some(&path),
+            )
+            .expect("bundled models load")
+        };
+
+        // The first load warms up and saves its state, the next restores it
+        let mut model = load();
+        assert!(path.exists());
+        let warm = model.get_preprocessor().snapshot();
+        assert_eq!(load().get_preprocessor().snapshot(), warm);
+
+        // Reset goes back to the warmed-up state
+        model
+            .get_preprocessor_mut()
+            .buffer_melspectrogram(&[500; 1280 * 3])
+            .unwrap();
+        assert_ne!(model.get_preprocessor().snapshot(), warm);
+        model.reset().unwrap();
+        assert_eq!(model.get_preprocessor().snapshot(), warm);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
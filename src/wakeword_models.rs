@@ -1,39 +1,522 @@
+//! Model manifests and discovery.
+//!
+//! Every model in the models directory is described by a JSON manifest next
+//! to it, e.g. `models/hey_mycroft.json`:
+//!
+//! ```json
+//! {
+//!   "name": "hey_mycroft",
+//!   "kind": "wakeword",
+//!   "path": "hey_mycroft_v0.1.tflite",
+//!   "labels": ["hey_mycroft"],
+//!   "threshold": 0.5,
+//!   "input_frames": 16,
+//!   "sha256": "bf9e4313..."
+//! }
+//! ```
+//!
+//! `kind` is `wakeword`, `melspectrogram` or `embedding`. `path` is relative
+//! to the manifest. Multi-class models list one label per output and may give
+//! an explicit `class_mapping` (output index -> label) instead. `threshold`,
+//! `input_frames` and `sha256` are optional; when present the threshold is the
+//! model's default operating point, and the other two are checked at load.
+//!
+//! Adding a wake word is a file drop: put the `.tflite` and its manifest in
+//! the models directory and pass the manifest's `name` to `--wakeword-model`.
+//! A name without a manifest still resolves to a `<name>.tflite` or
+//! `<name>_v<version>.tflite` in the directory, as the stock openWakeWord
+//! models did before manifests.
+
+use crate::wakeword_error::{OpenWakeWordError, Result};
+use crate::wakeword_gate::{DetectionOverride, DetectionSettings};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Feature model filenames used when the models directory has no manifest for them
+const DEFAULT_MELSPECTROGRAM_FILE: &str = "melspectrogram.tflite";
+const DEFAULT_EMBEDDING_FILE: &str = "embedding_model.tflite";
+
+/// What a model is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelKind {
+    Wakeword,
+    Melspectrogram,
+    Embedding,
+}
+
+impl ModelKind {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "wakeword" => Some(Self::Wakeword),
+            "melspectrogram" => Some(Self::Melspectrogram),
+            "embedding" => Some(Self::Embedding),
+            _ => None,
+        }
+    }
+}
+
+/// Description of one model file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelManifest {
+    pub name: String,
+    pub kind: ModelKind,
+    /// Path of the `.tflite` file
+    pub path: PathBuf,
+    /// Output index -> label, for multi-class models
+    pub class_mapping: HashMap<String, String>,
+    /// Recommended detection threshold
+    pub threshold: Option<f32>,
+    /// Number of embedding frames the model expects as input
+    pub input_frames: Option<usize>,
+    /// Lowercase hex SHA-256 of the `.tflite` file
+    pub sha256: Option<String>,
+}
+
+impl ModelManifest {
+    /// Load a manifest file. Relative model paths are resolved against its directory.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            OpenWakeWordError::ModelLoadError(format!(
+                "Failed to read manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| {
+            OpenWakeWordError::ConfigurationError(format!(
+                "Invalid manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_json(&value, base_dir).map_err(|e| {
+            OpenWakeWordError::ConfigurationError(format!(
+                "Invalid manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Build a manifest from parsed JSON
+    pub fn from_json(value: &Value, base_dir: &Path) -> std::result::Result<Self, String> {
+        let field = |key: &str| value.get(key).filter(|v| !v.is_null());
+
+        let name = field("name")
+            .and_then(Value::as_str)
+            .ok_or("missing \"name\"")?
+            .to_string();
+        let kind_str = field("kind").and_then(Value::as_str).unwrap_or("wakeword");
+        let kind = ModelKind::parse(kind_str).ok_or_else(|| {
+            format!(
+                "unknown kind \"{}\" (expected wakeword, melspectrogram or embedding)",
+                kind_str
+            )
+        })?;
+        let path = base_dir.join(
+            field("path")
+                .and_then(Value::as_str)
+                .ok_or("missing \"path\"")?,
+        );
+
+        let mut class_mapping = HashMap::new();
+        if let Some(labels) = field("labels") {
+            let labels = labels.as_array().ok_or("\"labels\" must be an array")?;
+            for (i, label) in labels.iter().enumerate() {
+                let label = label.as_str().ok_or("\"labels\" must contain strings")?;
+                class_mapping.insert(i.to_string(), label.to_string());
+            }
+        }
+        if let Some(mapping) = field("class_mapping") {
+            let mapping = mapping
+                .as_object()
+                .ok_or("\"class_mapping\" must be an object")?;
+            for (index, label) in mapping {
+                let label = label
+                    .as_str()
+                    .ok_or("\"class_mapping\" values must be strings")?;
+                class_mapping.insert(index.clone(), label.to_string());
+            }
+        }
+
+        let threshold = match field("threshold") {
+            Some(v) => Some(v.as_f64().ok_or("\"threshold\" must be a number")? as f32),
+            None => None,
+        };
+        let input_frames = match field("input_frames") {
+            Some(v) => Some(v.as_u64().ok_or("\"input_frames\" must be an integer")? as usize),
+            None => None,
+        };
+        let sha256 = match field("sha256") {
+            Some(v) => Some(
+                v.as_str()
+                    .ok_or("\"sha256\" must be a string")?
+                    .to_ascii_lowercase(),
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            name,
+            kind,
+            path,
+            class_mapping,
+            threshold,
+            input_frames,
+            sha256,
+        })
+    }
+
+    /// Manifest for a bare `.tflite` file with no metadata
+    pub fn for_tflite(path: &Path, kind: ModelKind) -> Result<Self> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| {
+                OpenWakeWordError::ModelLoadError(format!("Invalid model path: {}", path.display()))
+            })?;
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            path: path.to_path_buf(),
+            class_mapping: HashMap::new(),
+            threshold: None,
+            input_frames: None,
+            sha256: None,
+        })
+    }
+
+    /// Check `data` (the model file contents) against the manifest checksum, if any
+    pub fn verify_checksum(&self, data: &[u8]) -> Result<()> {
+        let Some(ref expected) = self.sha256 else {
+            return Ok(());
+        };
+        let actual = format!("{:x}", Sha256::digest(data));
+        if &actual != expected {
+            return Err(OpenWakeWordError::ModelLoadError(format!(
+                "Checksum mismatch for {} ({}): expected sha256 {}, got {}",
+                self.name,
+                self.path.display(),
+                expected,
+                actual
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read the model file and verify its checksum
+    pub fn read_verified(&self) -> Result<Vec<u8>> {
+        let data = std::fs::read(&self.path).map_err(|e| {
+            OpenWakeWordError::ModelLoadError(format!(
+                "Failed to read model file {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        self.verify_checksum(&data)?;
+        Ok(data)
+    }
+}
+
+/// All manifests found in a models directory
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    model_dir: PathBuf,
+    manifests: Vec<ModelManifest>,
+}
+
+impl ModelRegistry {
+    /// Load every `*.json` manifest in `model_dir`. Unreadable manifests are
+    /// logged and skipped so one bad file doesn't hide the rest.
+    pub fn discover(model_dir: &str) -> Result<Self> {
+        let entries = std::fs::read_dir(model_dir).map_err(|e| {
+            OpenWakeWordError::ModelLoadError(format!(
+                "Failed to read model directory '{}': {}",
+                model_dir, e
+            ))
+        })?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+            .collect();
+        paths.sort();
+
+        let mut manifests: Vec<ModelManifest> = Vec::new();
+        for path in paths {
+            match ModelManifest::from_file(&path) {
+                Ok(manifest) => {
+                    if manifests
+                        .iter()
+                        .any(|m| m.name == manifest.name && m.kind == manifest.kind)
+                    {
+                        return Err(OpenWakeWordError::ConfigurationError(format!(
+                            "Duplicate model name '{}' in {}",
+                            manifest.name,
+                            path.display()
+                        )));
+                    }
+                    log::debug!(
+                        "📄 Found {:?} model '{}' in {}",
+                        manifest.kind,
+                        manifest.name,
+                        path.display()
+                    );
+                    manifests.push(manifest);
+                }
+                Err(e) => log::warn!("⚠️ Skipping model manifest: {}", e),
+            }
+        }
+
+        Ok(Self {
+            model_dir: PathBuf::from(model_dir),
+            manifests,
+        })
+    }
+
+    /// Names of all wake word models with a manifest
+    pub fn wakeword_names(&self) -> Vec<&str> {
+        self.manifests
+            .iter()
+            .filter(|m| m.kind == ModelKind::Wakeword)
+            .map(|m| m.name.as_str())
+            .collect()
+    }
+
+    /// Resolve a wake word given as a manifest name, a manifest path, a bare
+    /// `.tflite` path or the name of a `.tflite` without a manifest
+    pub fn resolve_wakeword(&self, model: &str) -> Result<ModelManifest> {
+        let path = Path::new(model);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => return ModelManifest::from_file(path),
+            Some(_) => return ModelManifest::for_tflite(path, ModelKind::Wakeword),
+            None if path.components().count() > 1 => {
+                return ModelManifest::for_tflite(path, ModelKind::Wakeword)
+            }
+            None => {}
+        }
+
+        self.manifests
+            .iter()
+            .find(|m| m.kind == ModelKind::Wakeword && m.name == model)
+            .cloned()
+            .or_else(|| self.find_bare_tflite(model))
+            .ok_or_else(|| {
+                OpenWakeWordError::ModelLoadError(format!(
+                    "Unknown wake word model '{}' (available in '{}': {}; or a path to a .tflite or manifest file)",
+                    model,
+                    self.model_dir.display(),
+                    self.wakeword_names().join(", ")
+                ))
+            })
+    }
+
+    /// `<name>.tflite` or `<name>_v<version>.tflite` in the models directory,
+    /// newest version first, named `name` so scores keep the short name
+    fn find_bare_tflite(&self, name: &str) -> Option<ModelManifest> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.model_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("tflite"))
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.strip_prefix(name))
+                    .is_some_and(|version| version.is_empty() || version.starts_with("_v"))
+            })
+            .collect();
+        paths.sort();
+
+        let mut manifest = ModelManifest::for_tflite(&paths.pop()?, ModelKind::Wakeword).ok()?;
+        log::info!(
+            "📄 No manifest for wake word '{}', using {}",
+            name,
+            manifest.path.display()
+        );
+        manifest.name = name.to_string();
+        Some(manifest)
+    }
+
+    /// Manifest for a feature model, falling back to the stock openWakeWord
+    /// filename in the models directory when there is none
+    pub fn feature(&self, kind: ModelKind) -> Result<ModelManifest> {
+        if let Some(manifest) = self.manifests.iter().find(|m| m.kind == kind) {
+            return Ok(manifest.clone());
+        }
+        let filename = match kind {
+            ModelKind::Melspectrogram => DEFAULT_MELSPECTROGRAM_FILE,
+            ModelKind::Embedding => DEFAULT_EMBEDDING_FILE,
+            ModelKind::Wakeword => {
+                return Err(OpenWakeWordError::ConfigurationError(
+                    "Wake word models are not feature models".to_string(),
+                ))
+            }
+        };
+        ModelManifest::for_tflite(&self.model_dir.join(filename), kind)
+    }
+
+    /// Per-model detection settings for `wakeword_models`.
+    ///
+    /// Each model starts from `defaults`, takes its manifest threshold when
+    /// `manifest_thresholds` is set, then applies any entry in `overrides`.
+    pub fn detection_settings(
+        &self,
+        wakeword_models: &[String],
+        defaults: &DetectionSettings,
+        manifest_thresholds: bool,
+        overrides: &HashMap<String, DetectionOverride>,
+    ) -> HashMap<String, DetectionSettings> {
+        let mut settings = HashMap::new();
+        for model in wakeword_models {
+            let Ok(manifest) = self.resolve_wakeword(model) else {
+                continue;
+            };
+            let mut model_settings = *defaults;
+            if manifest_thresholds {
+                if let Some(threshold) = manifest.threshold {
+                    model_settings.threshold = threshold;
+                }
+            }
+            if let Some(o) = overrides.get(&manifest.name) {
+                model_settings = o.apply(&model_settings);
+            }
+            settings.insert(manifest.name, model_settings);
+        }
+        settings
+    }
+}
+
+/// Check that every model file needed to detect `wakeword_models` exists,
+/// including the shared feature models. Reports all missing files at once.
+pub fn validate_model_files(wakeword_models: &[String], model_dir: &str) -> Result<()> {
+    if wakeword_models.is_empty() {
+        return Err(OpenWakeWordError::ConfigurationError(
+            "No wake word models configured".to_string(),
+        ));
+    }
+
+    let registry = ModelRegistry::discover(model_dir)?;
+    let mut required = vec![
+        registry.feature(ModelKind::Melspectrogram)?,
+        registry.feature(ModelKind::Embedding)?,
+    ];
+    for model in wakeword_models {
+        required.push(registry.resolve_wakeword(model)?);
+    }
+
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|m| !m.path.is_file())
+        .map(|m| format!("{} ({})", m.name, m.path.display()))
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(OpenWakeWordError::ModelLoadError(format!(
+            "Missing model files in '{}': {}",
+            model_dir,
+            missing.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_manifest_from_json() {
+        let value = json!({
+            "name": "timer",
+            "path": "timer_v0.1.tflite",
+            "labels": ["1_minute_timer", "5_minute_timer"],
+            "class_mapping": {"1": "five_minutes"},
+            "threshold": 0.6,
+            "input_frames": 16,
+            "sha256": "ABCDEF",
+        });
+        let manifest = ModelManifest::from_json(&value, Path::new("models")).unwrap();
+
+        assert_eq!(manifest.kind, ModelKind::Wakeword);
+        assert_eq!(manifest.path, Path::new("models/timer_v0.1.tflite"));
+        assert_eq!(manifest.class_mapping["0"], "1_minute_timer");
+        assert_eq!(manifest.class_mapping["1"], "five_minutes");
+        assert_eq!(manifest.threshold, Some(0.6));
+        assert_eq!(manifest.input_frames, Some(16));
+        assert_eq!(manifest.sha256.as_deref(), Some("abcdef"));
+    }
+
+    #[test]
+    fn test_manifest_rejects_invalid() {
+        let base = Path::new("models");
+        assert!(ModelManifest::from_json(&json!({"path": "x.tflite"}), base).is_err());
+        assert!(ModelManifest::from_json(&json!({"name": "x"}), base).is_err());
+        assert!(ModelManifest::from_json(
+            &json!({"name": "x", "path": "x.tflite", "kind": "classifier"}),
+            base
+        )
+        .is_err());
+        assert!(ModelManifest::from_json(
+            &json!({"name": "x", "path": "x.tflite", "threshold": "high"}),
+            base
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_resolve_bare_tflite_by_name() {
+        let dir = std::env::temp_dir().join(format!("ww_models_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["alexa_v0.1.tflite", "alexa_v0.2.tflite", "alexandra.tflite"] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        let registry = ModelRegistry::discover(dir.to_str().unwrap()).unwrap();
+
+        let alexa = registry.resolve_wakeword("alexa").unwrap();
+        assert_eq!(alexa.name, "alexa");
+        assert_eq!(alexa.path, dir.join("alexa_v0.2.tflite"));
+        assert_eq!(
+            registry.resolve_wakeword("alexandra").unwrap().name,
+            "alexandra"
+        );
+        assert!(registry.resolve_wakeword("alex").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stock_model_names_resolve() {
+        let registry =
+            ModelRegistry::discover(concat!(env!("CARGO_MANIFEST_DIR"), "/models")).unwrap();
+        for name in [
+            "alexa",
+            "hey_mycroft",
+            "hey_jarvis",
+            "hey_rhasspy",
+            "timer",
+            "weather",
+        ] {
+            assert_eq!(registry.resolve_wakeword(name).unwrap().name, name);
+        }
+        let timer = registry.resolve_wakeword("timer").unwrap();
+        assert_eq!(timer.class_mapping.len(), 6);
+        assert_eq!(timer.class_mapping["6"], "1_hour_timer");
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let mut manifest =
+            ModelManifest::for_tflite(Path::new("x.tflite"), ModelKind::Wakeword).unwrap();
+        assert!(manifest.verify_checksum(b"abc").is_ok());
 
-/// Feature models configuration (melspectrogram and embedding)
-pub const FEATURE_MODELS: &[(&str, &str)] = &[
-    ("embedding", "models/embedding_model.tflite"),
-    ("melspectrogram", "models/melspectrogram.tflite"),
-];
-
-/// Available wake word models
-pub const MODELS: &[(&str, &str)] = &[
-    ("alexa", "models/alexa_v0.1.tflite"),
-    ("hey_mycroft", "models/hey_mycroft_v0.1.tflite"),
-    ("hey_jarvis", "models/hey_jarvis_v0.1.tflite"),
-    ("hey_rhasspy", "models/hey_rhasspy_v0.1.tflite"),
-    ("timer", "models/timer_v0.1.tflite"),
-    ("weather", "models/weather_v0.1.tflite"),
-];
-
-/// Get pre-trained model paths for TFLite models
-pub fn get_pretrained_model_paths() -> Vec<String> {
-    MODELS.iter().map(|(_, path)| path.to_string()).collect()
-}
-
-/// Default model class mappings for multi-class models
-pub fn get_model_class_mappings() -> HashMap<String, HashMap<String, String>> {
-    let mut mappings = HashMap::new();
-
-    // Timer model has multiple classes
-    let mut timer_mapping = HashMap::new();
-    timer_mapping.insert("1".to_string(), "1_minute_timer".to_string());
-    timer_mapping.insert("2".to_string(), "5_minute_timer".to_string());
-    timer_mapping.insert("3".to_string(), "10_minute_timer".to_string());
-    timer_mapping.insert("4".to_string(), "20_minute_timer".to_string());
-    timer_mapping.insert("5".to_string(), "30_minute_timer".to_string());
-    timer_mapping.insert("6".to_string(), "1_hour_timer".to_string());
-    mappings.insert("timer".to_string(), timer_mapping);
-
-    mappings
+        manifest.sha256 =
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string());
+        assert!(manifest.verify_checksum(b"abc").is_ok());
+        assert!(manifest.verify_checksum(b"abd").is_err());
+    }
 }
@@ -4,9 +4,27 @@
 //! including streaming audio processing and buffer management.
 
 use crate::wakeword_error::{OpenWakeWordError, Result};
+use crate::wakeword_interpreter::OwnedInterpreter;
+use sha2::{Digest, Sha256};
 use std::collections::VecDeque;
+use std::path::Path;
 use tflitec::tensor::Shape;
-use tflitec::{interpreter::Interpreter, model::Model as TfliteModel};
+
+/// Leading bytes of a persisted `FeatureSnapshot`
+const SNAPSHOT_MAGIC: &[u8; 4] = b"OWWF";
+/// Version of the snapshot file format written by this crate
+const SNAPSHOT_VERSION: u8 = 1;
+/// Samples before a chunk that the melspectrogram needs as context
+const MEL_CONTEXT_SAMPLES: usize = 160 * 3;
+/// Mel frames in one embedding window
+const EMBEDDING_WINDOW_FRAMES: usize = 76;
+/// Values per mel frame
+const MEL_BINS: usize = 32;
+/// Values per embedding
+const EMBEDDING_DIM: usize = 96;
+/// Upper bound on frames per buffer in a snapshot file, above the 970 mel
+/// frames `AudioFeatures` ever keeps
+const MAX_SNAPSHOT_FRAMES: usize = 1024;
 
 /// AudioFeatures class for creating audio features from audio data
 ///
@@ -15,8 +33,8 @@ use tflitec::{interpreter::Interpreter, model::Model as TfliteModel};
 /// - Call `audio_features.get_features(n_frames, start_ndx)` to extract features
 pub struct AudioFeatures {
     // TensorFlow Lite models
-    melspec_model: Interpreter<'static>,
-    embedding_model: Interpreter<'static>,
+    melspec_model: OwnedInterpreter,
+    embedding_model: OwnedInterpreter,
 
     // Streaming buffers (matching Python implementation)
     raw_data_buffer: VecDeque<i16>,
@@ -24,9 +42,12 @@ pub struct AudioFeatures {
     accumulated_samples: usize,
     raw_data_remainder: Vec<i16>,
     feature_buffer: VecDeque<Vec<f32>>, // Stores embeddings
+    skipped_embeddings: usize,          // Chunks buffered by `buffer_melspectrogram` only
 
     // Configuration
     feature_buffer_max_len: usize, // 120 frames (~10 seconds)
+    model_digest: [u8; 32],        // Identifies the models snapshots belong to
+    reset_state: FeatureSnapshot,  // Restored by `reset`
 }
 
 impl AudioFeatures {
@@ -37,27 +58,81 @@ impl AudioFeatures {
     /// * `embedding_model_path` - Path to embedding model  
     /// * `sr` - Sample rate (default: 16000)
     pub fn new(melspec_model_path: &str, embedding_model_path: &str, sr: u32) -> Result<Self> {
-        // Load melspectrogram model
-        let melspec_model_data = Box::leak(Box::new(
-            TfliteModel::new(melspec_model_path).map_err(|e| {
-                OpenWakeWordError::ModelLoadError(format!("Failed to load melspec model: {}", e))
-            })?,
-        ));
+        let mut instance = Self::load(melspec_model_path, embedding_model_path, sr)?;
+        instance.warm_up()?;
+        instance.reset_state = instance.snapshot();
+        Ok(instance)
+    }
+
+    /// Create an AudioFeatures instance from a snapshot instead of running
+    /// the warmup inference. The snapshot also becomes the `reset` state.
+    pub fn from_snapshot(
+        melspec_model_path: &str,
+        embedding_model_path: &str,
+        sr: u32,
+        snapshot: &FeatureSnapshot,
+    ) -> Result<Self> {
+        let mut instance = Self::load(melspec_model_path, embedding_model_path, sr)?;
+        instance.restore(snapshot)?;
+        instance.reset_state = snapshot.clone();
+        Ok(instance)
+    }
+
+    /// Create an AudioFeatures instance, restoring the snapshot at
+    /// `snapshot_path` when it was taken with the same models. Otherwise warm
+    /// up as `new` does and try to save the result there for next time.
+    pub fn with_snapshot_file(
+        melspec_model_path: &str,
+        embedding_model_path: &str,
+        sr: u32,
+        snapshot_path: &Path,
+    ) -> Result<Self> {
+        let mut instance = Self::load(melspec_model_path, embedding_model_path, sr)?;
+
+        if snapshot_path.exists() {
+            match FeatureSnapshot::load(snapshot_path).and_then(|s| instance.restore(&s)) {
+                Ok(()) => {
+                    log::info!("⚡ Restored feature state from {}", snapshot_path.display());
+                    instance.reset_state = instance.snapshot();
+                    return Ok(instance);
+                }
+                Err(e) => log::warn!(
+                    "⚠️  Ignoring feature snapshot {}: {}",
+                    snapshot_path.display(),
+                    e
+                ),
+            }
+        }
+
+        instance.warm_up()?;
+        instance.reset_state = instance.snapshot();
+        match instance.reset_state.save(snapshot_path) {
+            Ok(()) => log::info!("💾 Saved feature state to {}", snapshot_path.display()),
+            Err(e) => log::warn!(
+                "⚠️  Could not save feature snapshot {}: {}",
+                snapshot_path.display(),
+                e
+            ),
+        }
+        Ok(instance)
+    }
+
+    /// Load both interpreters and set up empty streaming buffers
+    fn load(melspec_model_path: &str, embedding_model_path: &str, sr: u32) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(std::fs::read(melspec_model_path)?);
+        hasher.update(std::fs::read(embedding_model_path)?);
+        let model_digest: [u8; 32] = hasher.finalize().into();
 
         // Match Python's default single-threaded configuration for stability
         let mut options = tflitec::interpreter::Options::default();
         options.thread_count = 1; // Match Python's default (ncpu=1)
         options.is_xnnpack_enabled = true; // Keep XNNPACK for performance
 
-        let melspec_model =
-            tflitec::interpreter::Interpreter::new(melspec_model_data, Some(options)).map_err(
-                |e| {
-                    OpenWakeWordError::ModelLoadError(format!(
-                        "Failed to create melspec interpreter: {}",
-                        e
-                    ))
-                },
-            )?;
+        // Load melspectrogram model
+        let melspec_model = OwnedInterpreter::new(melspec_model_path, options).map_err(|e| {
+            OpenWakeWordError::ModelLoadError(format!("Failed to load melspec model: {}", e))
+        })?;
 
         // Resize melspec input tensor to a reasonable shape before allocating tensors
         // This avoids the integer overflow issue with the default model shape
@@ -72,25 +147,15 @@ impl AudioFeatures {
             OpenWakeWordError::ModelLoadError(format!("Failed to allocate melspec tensors: {}", e))
         })?;
 
-        // Load embedding model with multi-core optimization
-        let embedding_model_data = Box::leak(Box::new(
-            TfliteModel::new(embedding_model_path).map_err(|e| {
-                OpenWakeWordError::ModelLoadError(format!("Failed to load embedding model: {}", e))
-            })?,
-        ));
-
         // Match Python's single-threaded configuration for both models
         let mut embedding_options = tflitec::interpreter::Options::default();
         embedding_options.thread_count = 1; // Match Python's default (ncpu=1)
         embedding_options.is_xnnpack_enabled = true; // Keep XNNPACK for performance
 
-        let embedding_model =
-            tflitec::interpreter::Interpreter::new(embedding_model_data, Some(embedding_options))
-                .map_err(|e| {
-                OpenWakeWordError::ModelLoadError(format!(
-                    "Failed to create embedding interpreter: {}",
-                    e
-                ))
+        // Load embedding model
+        let embedding_model = OwnedInterpreter::new(embedding_model_path, embedding_options)
+            .map_err(|e| {
+                OpenWakeWordError::ModelLoadError(format!("Failed to load embedding model: {}", e))
             })?;
 
         // Resize embedding input tensor to the correct shape: [1, 76, 32, 1]
@@ -113,7 +178,7 @@ impl AudioFeatures {
         })?;
 
         // Initialize buffers (matching Python implementation)
-        let mut instance = AudioFeatures {
+        Ok(AudioFeatures {
             melspec_model,
             embedding_model,
             raw_data_buffer: VecDeque::with_capacity(sr as usize * 10), // 10 seconds
@@ -128,41 +193,106 @@ impl AudioFeatures {
             accumulated_samples: 0,
             raw_data_remainder: Vec::new(),
             feature_buffer: VecDeque::new(),
+            skipped_embeddings: 0,
             feature_buffer_max_len: 120, // ~10 seconds
-        };
+            model_digest,
+            reset_state: FeatureSnapshot::default(),
+        })
+    }
 
-        // Initialize feature buffer with embeddings from random noise (matches Python:
-        // self.feature_buffer = self._get_embeddings(np.random.randint(-1000, 1000, 16000*4)))
+    /// Initialize feature buffer with embeddings from random noise (matches Python:
+    /// self.feature_buffer = self._get_embeddings(np.random.randint(-1000, 1000, 16000*4)))
+    fn warm_up(&mut self) -> Result<()> {
         let warmup_noise = Self::generate_warmup_noise(16000 * 4);
-        let warmup_embeddings = instance._get_embeddings(&warmup_noise)?;
+        let warmup_embeddings = self._get_embeddings(&warmup_noise)?;
+        self.feature_buffer.clear();
         for emb in warmup_embeddings {
-            instance.feature_buffer.push_back(emb);
+            self.feature_buffer.push_back(emb);
         }
-
-        Ok(instance)
+        Ok(())
     }
 
-    /// Reset the internal buffers
+    /// Reset the internal buffers to the state after warmup, or to the last
+    /// `set_reset_state`. No inference runs.
     pub fn reset(&mut self) -> Result<()> {
-        self.raw_data_buffer.clear();
-        self.melspectrogram_buffer.clear();
-        for _ in 0..76 {
-            self.melspectrogram_buffer.push_back(vec![1.0; 32]);
+        let state = self.reset_state.clone();
+        self.restore(&state)
+    }
+
+    /// Capture everything the next chunk depends on. Cheap: copies at most
+    /// a few hundred kB of buffers and runs no inference.
+    pub fn snapshot(&self) -> FeatureSnapshot {
+        let raw_needed = self.accumulated_samples + MEL_CONTEXT_SAMPLES;
+        let mel_skip = self
+            .melspectrogram_buffer
+            .len()
+            .saturating_sub(EMBEDDING_WINDOW_FRAMES);
+
+        FeatureSnapshot {
+            model_digest: self.model_digest,
+            raw_tail: self
+                .raw_data_buffer
+                .iter()
+                .skip(self.raw_data_buffer.len().saturating_sub(raw_needed))
+                .copied()
+                .collect(),
+            accumulated_samples: self.accumulated_samples,
+            remainder: self.raw_data_remainder.clone(),
+            melspectrogram: self
+                .melspectrogram_buffer
+                .iter()
+                .skip(mel_skip)
+                .cloned()
+                .collect(),
+            features: self.feature_buffer.iter().cloned().collect(),
         }
-        self.accumulated_samples = 0;
-        self.raw_data_remainder.clear();
+    }
 
-        // Reinitialize feature buffer with embeddings from random noise (matches Python)
+    /// Replace the streaming state with `snapshot`. Embeddings skipped by
+    /// `buffer_melspectrogram` before the restore are forgotten.
+    pub fn restore(&mut self, snapshot: &FeatureSnapshot) -> Result<()> {
+        self.check_snapshot(snapshot)?;
+
+        self.raw_data_buffer.clear();
+        self.raw_data_buffer
+            .extend(snapshot.raw_tail.iter().copied());
+        self.accumulated_samples = snapshot.accumulated_samples;
+        self.raw_data_remainder.clone_from(&snapshot.remainder);
+        self.melspectrogram_buffer.clear();
+        self.melspectrogram_buffer
+            .extend(snapshot.melspectrogram.iter().cloned());
         self.feature_buffer.clear();
-        let warmup_noise = Self::generate_warmup_noise(16000 * 4);
-        let warmup_embeddings = self._get_embeddings(&warmup_noise)?;
-        for emb in warmup_embeddings {
-            self.feature_buffer.push_back(emb);
-        }
+        self.feature_buffer
+            .extend(snapshot.features.iter().cloned());
+        self.skipped_embeddings = 0;
+        Ok(())
+    }
 
+    /// Make `reset` return to `snapshot`, e.g. one taken after a stretch of
+    /// real room tone instead of the synthetic warmup noise
+    pub fn set_reset_state(&mut self, snapshot: FeatureSnapshot) -> Result<()> {
+        self.check_snapshot(&snapshot)?;
+        self.reset_state = snapshot;
         Ok(())
     }
 
+    /// The state `reset` returns to
+    pub fn reset_state(&self) -> &FeatureSnapshot {
+        &self.reset_state
+    }
+
+    /// Reject snapshots of other models or whose buffers don't fit this
+    /// instance
+    fn check_snapshot(&self, snapshot: &FeatureSnapshot) -> Result<()> {
+        if snapshot.model_digest != self.model_digest {
+            return Err(OpenWakeWordError::ConfigurationError(
+                "feature snapshot was taken with different melspectrogram or embedding models"
+                    .to_string(),
+            ));
+        }
+        snapshot.check_shape(self.feature_buffer_max_len)
+    }
+
     /// Generate deterministic pseudo-random noise for model warmup.
     /// Matches Python: np.random.randint(-1000, 1000, n_samples).astype(np.int16)
     fn generate_warmup_noise(n_samples: usize) -> Vec<i16> {
@@ -186,7 +316,46 @@ impl AudioFeatures {
     /// # Returns
     /// * Number of prepared samples
     pub fn __call__(&mut self, x: &[i16]) -> Result<usize> {
-        self._streaming_features(x)
+        self._streaming_features(x, true)
+    }
+
+    /// Process audio like `__call__`, but only update the melspectrogram
+    /// buffer. The embeddings of the skipped chunks can be computed later
+    /// with `backfill_embeddings` as long as their mel frames are still buffered.
+    pub fn buffer_melspectrogram(&mut self, x: &[i16]) -> Result<usize> {
+        self._streaming_features(x, false)
+    }
+
+    /// Compute the embeddings of up to `max_chunks` of the most recent chunks
+    /// skipped by `buffer_melspectrogram`, oldest first, so the newest
+    /// `max_chunks` feature frames match what `__call__` would have produced.
+    /// Older skipped chunks are dropped. Returns the number of embeddings computed.
+    pub fn backfill_embeddings(&mut self, max_chunks: usize) -> Result<usize> {
+        let skipped = std::mem::take(&mut self.skipped_embeddings);
+        let mel_frames = self.melspectrogram_buffer.len();
+        if mel_frames < 76 {
+            return Ok(0);
+        }
+        // Chunk i (0 = newest) ends 8*i mel frames before the end of the buffer
+        let available = (mel_frames - 76) / 8 + 1;
+        let count = skipped.min(max_chunks).min(available);
+
+        for i in (0..count).rev() {
+            let end_idx = mel_frames - 8 * i;
+            let melspec_window: Vec<f32> = self
+                .melspectrogram_buffer
+                .range(end_idx - 76..end_idx)
+                .flat_map(|frame| frame.iter().copied())
+                .collect();
+            let embedding = self._get_embeddings_from_melspec(&melspec_window)?;
+            self.feature_buffer.push_back(embedding);
+            if self.feature_buffer.len() > self.feature_buffer_max_len {
+                self.feature_buffer.pop_front();
+            }
+        }
+
+        log::debug!("🔍 Backfilled {} of {} skipped embeddings", count, skipped);
+        Ok(count)
     }
 
     /// Get features for model prediction (matches Python get_features)
@@ -246,6 +415,18 @@ impl AudioFeatures {
             .collect()
     }
 
+    /// Melspectrogram frames of `x`, flattened 32 bins per frame, without
+    /// touching the streaming buffers
+    pub fn melspectrogram(&mut self, x: &[i16]) -> Result<Vec<f32>> {
+        self._get_melspectrogram(x)
+    }
+
+    /// Embeddings of every 76-frame window of `x`, 8 frames apart, without
+    /// touching the streaming buffers
+    pub fn embeddings(&mut self, x: &[i16]) -> Result<Vec<Vec<f32>>> {
+        self._get_embeddings(x)
+    }
+
     /// Compute melspectrogram from audio data
     fn _get_melspectrogram(&mut self, x: &[i16]) -> Result<Vec<f32>> {
         // Convert to float and reshape
@@ -451,8 +632,9 @@ impl AudioFeatures {
         // Note: accumulated_samples is managed separately in _streaming_features
     }
 
-    /// Process streaming audio features (matches Python _streaming_features)
-    fn _streaming_features(&mut self, x: &[i16]) -> Result<usize> {
+    /// Process streaming audio features (matches Python _streaming_features).
+    /// With `compute_embeddings` false only the melspectrogram is updated.
+    fn _streaming_features(&mut self, x: &[i16], compute_embeddings: bool) -> Result<usize> {
         log::debug!(
             "🔍 _streaming_features: input={} samples, accumulated={}, remainder={}, buffer={}",
             x.len(),
@@ -535,7 +717,9 @@ impl AudioFeatures {
 
             // Compute embeddings for each new chunk
             // Python: for i in np.arange(accumulated_samples//1280-1, -1, -1)
-            if self.melspectrogram_buffer.len() >= 76 {
+            if !compute_embeddings {
+                self.skipped_embeddings += self.accumulated_samples / chunk_size;
+            } else if self.melspectrogram_buffer.len() >= 76 {
                 let new_chunks = self.accumulated_samples / chunk_size;
 
                 for i in (0..new_chunks).rev() {
@@ -575,3 +759,223 @@ impl AudioFeatures {
         })
     }
 }
+
+/// Streaming state of `AudioFeatures`: the raw audio, mel frames and
+/// embeddings the next chunk builds on.
+///
+/// Restoring one replaces the warmup inference of `AudioFeatures::new` and
+/// `reset`. A snapshot belongs to the melspectrogram and embedding models it
+/// was taken with and is rejected by any others.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureSnapshot {
+    /// SHA-256 over the melspectrogram and embedding model files
+    model_digest: [u8; 32],
+    /// Unprocessed samples of the current chunk plus melspectrogram context
+    raw_tail: Vec<i16>,
+    accumulated_samples: usize,
+    remainder: Vec<i16>,
+    /// Newest mel frames, enough for the next embedding window
+    melspectrogram: Vec<Vec<f32>>,
+    features: Vec<Vec<f32>>,
+}
+
+impl FeatureSnapshot {
+    /// Reject snapshots with too few mel frames to continue streaming from,
+    /// or with embeddings that aren't `EMBEDDING_DIM` wide or outnumber
+    /// `max_features`
+    fn check_shape(&self, max_features: usize) -> Result<()> {
+        if self.melspectrogram.len() < EMBEDDING_WINDOW_FRAMES
+            || self.melspectrogram.iter().any(|f| f.len() != MEL_BINS)
+        {
+            return Err(OpenWakeWordError::InvalidInput(format!(
+                "feature snapshot needs {} mel frames of {} bins",
+                EMBEDDING_WINDOW_FRAMES, MEL_BINS
+            )));
+        }
+        if self.features.len() > max_features
+            || self.features.iter().any(|f| f.len() != EMBEDDING_DIM)
+        {
+            return Err(OpenWakeWordError::InvalidInput(format!(
+                "feature snapshot needs at most {} embeddings of {} values",
+                max_features, EMBEDDING_DIM
+            )));
+        }
+        Ok(())
+    }
+
+    /// Serialize as `magic, version, digest, accumulated u32, raw_tail,
+    /// remainder, melspectrogram, features`, little-endian. Sample lists are
+    /// `[count u32][i16...]`, frame lists `[count u32][width u32][f32...]`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SNAPSHOT_MAGIC);
+        bytes.push(SNAPSHOT_VERSION);
+        bytes.extend_from_slice(&self.model_digest);
+        bytes.extend_from_slice(&(self.accumulated_samples as u32).to_le_bytes());
+        for samples in [&self.raw_tail, &self.remainder] {
+            bytes.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+            for sample in samples {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        for frames in [&self.melspectrogram, &self.features] {
+            let width = frames.first().map_or(0, Vec::len);
+            bytes.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(width as u32).to_le_bytes());
+            for value in frames.iter().flatten() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Parse bytes written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = bytes;
+        let header = take(&mut reader, 5)?;
+        if &header[..4] != SNAPSHOT_MAGIC {
+            return Err(OpenWakeWordError::InvalidInput(
+                "not a feature snapshot".to_string(),
+            ));
+        }
+        if header[4] != SNAPSHOT_VERSION {
+            return Err(OpenWakeWordError::InvalidInput(format!(
+                "unsupported feature snapshot version {}",
+                header[4]
+            )));
+        }
+
+        let mut model_digest = [0u8; 32];
+        model_digest.copy_from_slice(take(&mut reader, 32)?);
+        let accumulated_samples = read_u32(&mut reader)? as usize;
+        let raw_tail = read_samples(&mut reader)?;
+        let remainder = read_samples(&mut reader)?;
+        let melspectrogram = read_frames(&mut reader)?;
+        let features = read_frames(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(OpenWakeWordError::InvalidInput(format!(
+                "{} trailing bytes after feature snapshot",
+                reader.len()
+            )));
+        }
+
+        Ok(Self {
+            model_digest,
+            raw_tail,
+            accumulated_samples,
+            remainder,
+            melspectrogram,
+            features,
+        })
+    }
+
+    /// Write the snapshot to `path`, replacing any previous file atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, self.to_bytes())?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Read a snapshot written by `save`
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Split the next `len` bytes off `reader`
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if len > reader.len() {
+        return Err(OpenWakeWordError::InvalidInput(
+            "truncated feature snapshot".to_string(),
+        ));
+    }
+    let (head, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(head)
+}
+
+fn read_u32(reader: &mut &[u8]) -> Result<u32> {
+    let b = take(reader, 4)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_samples(reader: &mut &[u8]) -> Result<Vec<i16>> {
+    let count = read_u32(reader)? as usize;
+    Ok(take(reader, count.saturating_mul(2))?
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect())
+}
+
+fn read_frames(reader: &mut &[u8]) -> Result<Vec<Vec<f32>>> {
+    let count = read_u32(reader)? as usize;
+    let width = read_u32(reader)? as usize;
+    if count > MAX_SNAPSHOT_FRAMES {
+        return Err(OpenWakeWordError::InvalidInput(format!(
+            "feature snapshot has {} frames in one buffer",
+            count
+        )));
+    }
+    let bytes = take(reader, count.saturating_mul(width).saturating_mul(4))?;
+    Ok((0..count)
+        .map(|i| {
+            bytes[i * width * 4..(i + 1) * width * 4]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> FeatureSnapshot {
+        FeatureSnapshot {
+            model_digest: [7; 32],
+            raw_tail: vec![1, -2, 3],
+            accumulated_samples: 640,
+            remainder: vec![i16::MIN, i16::MAX],
+            melspectrogram: vec![vec![0.5; MEL_BINS]; EMBEDDING_WINDOW_FRAMES],
+            features: vec![vec![-1.25; EMBEDDING_DIM]; 41],
+        }
+    }
+
+    #[test]
+    fn test_snapshot_binary() {
+        let snapshot = snapshot();
+        let bytes = snapshot.to_bytes();
+        assert_eq!(FeatureSnapshot::from_bytes(&bytes).unwrap(), snapshot);
+
+        // Truncated, trailing garbage, wrong magic and future versions fail
+        assert!(FeatureSnapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(FeatureSnapshot::from_bytes(&longer).is_err());
+        let mut other = bytes.clone();
+        other[0] = b'X';
+        assert!(FeatureSnapshot::from_bytes(&other).is_err());
+        other = bytes;
+        other[4] = SNAPSHOT_VERSION + 1;
+        assert!(FeatureSnapshot::from_bytes(&other).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_shape() {
+        assert!(snapshot().check_shape(120).is_ok());
+
+        let mut short_mel = snapshot();
+        short_mel.melspectrogram.pop();
+        assert!(short_mel.check_shape(120).is_err());
+
+        let mut narrow = snapshot();
+        narrow.features[3].pop();
+        assert!(narrow.check_shape(120).is_err());
+
+        let mut too_many = snapshot();
+        too_many.features = vec![vec![0.0; EMBEDDING_DIM]; 121];
+        assert!(too_many.check_shape(120).is_err());
+    }
+}
@@ -1,31 +1,390 @@
+use crate::protocol::UtteranceEndReason;
 use crate::wakeword_error::VadError;
 use log::{debug, info};
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "vad")]
 use voice_activity_detector::VoiceActivityDetector;
 
+/// Speech detector behind `VadProcessor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadBackend {
+    /// Silero neural VAD via ONNX Runtime
+    Silero,
+    /// Energy and zero-crossing detector; no model, negligible CPU
+    Energy,
+}
+
+impl FromStr for VadBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "silero" => Ok(VadBackend::Silero),
+            "energy" => Ok(VadBackend::Energy),
+            other => Err(format!(
+                "unknown VAD backend '{}' (expected silero or energy)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for VadBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VadBackend::Silero => write!(f, "silero"),
+            VadBackend::Energy => write!(f, "energy"),
+        }
+    }
+}
+
+impl Default for VadBackend {
+    /// Silero when built with the `vad` feature, otherwise the energy detector
+    fn default() -> Self {
+        if cfg!(feature = "vad") {
+            VadBackend::Silero
+        } else {
+            VadBackend::Energy
+        }
+    }
+}
+
 /// Configuration for Voice Activity Detection
 #[derive(Debug, Clone)]
 pub struct VadConfig {
+    /// Which speech detector to run
+    pub backend: VadBackend,
     /// Sample rate (should match audio chunks)
     pub sample_rate: u32,
     /// Chunk size for VAD processing (must be 512 for Silero VAD)
     pub chunk_size: usize,
     /// Speech detection threshold (0.0 to 1.0)
     pub speech_threshold: f32,
+    /// Continuous speech required before `SpeechStarted` is emitted
+    pub onset_ms: u64,
+    /// Continuous silence required before `SpeechStopped` is emitted
+    pub hangover_ms: u64,
 }
 
 impl Default for VadConfig {
     fn default() -> Self {
         Self {
+            backend: VadBackend::default(),
             sample_rate: 16000,    // 16kHz sample rate
             chunk_size: 512,       // 32ms chunks at 16kHz (required by Silero VAD)
             speech_threshold: 0.5, // Default threshold for speech detection
+            onset_ms: 96,          // 3 VAD windows
+            hangover_ms: 800,      // Bridges pauses between words
+        }
+    }
+}
+
+/// VAD result for one 512-sample window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadWindow {
+    /// Capture sample index of the first sample in the window
+    pub sample_index: u64,
+    /// Silero speech probability (0.0 to 1.0)
+    pub probability: f32,
+    /// `probability` compared against `speech_threshold`
+    pub has_speech: bool,
+}
+
+/// Speech boundary found by `SpeechSegmenter`, at the first sample of the
+/// first speech (or silent) window of the run that triggered it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechEvent {
+    Started { sample_index: u64 },
+    Stopped { sample_index: u64 },
+}
+
+/// Turns per-window VAD decisions into speech start/stop events. Speech must
+/// last `onset_ms` before it starts a segment and silence must last
+/// `hangover_ms` before it ends one, so VAD flicker doesn't split a phrase.
+pub struct SpeechSegmenter {
+    window_samples: u64,
+    onset_samples: u64,
+    hangover_samples: u64,
+    in_speech: bool,
+    /// Start of the current run of windows that disagree with `in_speech`
+    run_start: Option<u64>,
+}
+
+impl SpeechSegmenter {
+    pub fn new(config: &VadConfig) -> Self {
+        let samples = |ms: u64| ms * config.sample_rate as u64 / 1000;
+        Self {
+            window_samples: config.chunk_size as u64,
+            onset_samples: samples(config.onset_ms),
+            hangover_samples: samples(config.hangover_ms),
+            in_speech: false,
+            run_start: None,
+        }
+    }
+
+    /// Whether a speech segment is currently open
+    pub fn in_speech(&self) -> bool {
+        self.in_speech
+    }
+
+    /// Feed one window, returning the boundary it completes, if any
+    pub fn push(&mut self, window: &VadWindow) -> Option<SpeechEvent> {
+        if window.has_speech == self.in_speech {
+            self.run_start = None;
+            return None;
+        }
+
+        let run_start = *self.run_start.get_or_insert(window.sample_index);
+        let run_samples = window.sample_index + self.window_samples - run_start;
+        let needed = if self.in_speech {
+            self.hangover_samples
+        } else {
+            self.onset_samples
+        };
+        if run_samples < needed {
+            return None;
+        }
+
+        self.in_speech = !self.in_speech;
+        self.run_start = None;
+        Some(if self.in_speech {
+            SpeechEvent::Started {
+                sample_index: run_start,
+            }
+        } else {
+            SpeechEvent::Stopped {
+                sample_index: run_start,
+            }
+        })
+    }
+
+    /// Forget any open segment
+    pub fn reset(&mut self) {
+        self.in_speech = false;
+        self.run_start = None;
+    }
+}
+
+/// Timeouts for finding the end of the utterance that follows a wake word
+#[derive(Debug, Clone, Copy)]
+pub struct EndpointConfig {
+    /// Silence after speech that ends the utterance
+    pub min_silence_ms: u64,
+    /// Longest utterance, measured from the start of speech
+    pub max_utterance_ms: u64,
+    /// Give up if no speech starts within this time after the wake word
+    pub no_speech_timeout_ms: u64,
+}
+
+impl Default for EndpointConfig {
+    fn default() -> Self {
+        Self {
+            min_silence_ms: 1000,
+            max_utterance_ms: 15000,
+            no_speech_timeout_ms: 5000,
+        }
+    }
+}
+
+/// Utterance found by `Endpointer`. Without speech, `start_sample` is where
+/// listening began.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utterance {
+    pub start_sample: u64,
+    pub end_sample: u64,
+    pub reason: UtteranceEndReason,
+}
+
+/// Listening state between a wake word and the end of the utterance
+#[derive(Debug, Clone, Copy)]
+struct Listening {
+    since: u64,
+    speech_start: Option<u64>,
+    silence_start: Option<u64>,
+}
+
+/// Tracks VAD windows after a wake word and decides when the user stopped talking
+pub struct Endpointer {
+    window_samples: u64,
+    min_silence_samples: u64,
+    max_utterance_samples: u64,
+    no_speech_samples: u64,
+    listening: Option<Listening>,
+}
+
+impl Endpointer {
+    pub fn new(config: &EndpointConfig, vad_config: &VadConfig) -> Self {
+        let samples = |ms: u64| ms * vad_config.sample_rate as u64 / 1000;
+        Self {
+            window_samples: vad_config.chunk_size as u64,
+            min_silence_samples: samples(config.min_silence_ms),
+            max_utterance_samples: samples(config.max_utterance_ms),
+            no_speech_samples: samples(config.no_speech_timeout_ms),
+            listening: None,
+        }
+    }
+
+    /// Start listening for an utterance from `sample_index`, dropping any in progress
+    pub fn start(&mut self, sample_index: u64) {
+        self.listening = Some(Listening {
+            since: sample_index,
+            speech_start: None,
+            silence_start: None,
+        });
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening.is_some()
+    }
+
+    /// Feed one window, returning the utterance once it is complete
+    pub fn push(&mut self, window: &VadWindow) -> Option<Utterance> {
+        let listening = self.listening.as_mut()?;
+        if window.sample_index < listening.since {
+            return None;
+        }
+        let window_end = window.sample_index + self.window_samples;
+
+        if window.has_speech {
+            listening.speech_start.get_or_insert(window.sample_index);
+            listening.silence_start = None;
+        } else if listening.speech_start.is_some() {
+            listening.silence_start.get_or_insert(window.sample_index);
+        }
+
+        let utterance = match (listening.speech_start, listening.silence_start) {
+            (None, _) if window_end - listening.since >= self.no_speech_samples => Utterance {
+                start_sample: listening.since,
+                end_sample: window_end,
+                reason: UtteranceEndReason::NoSpeech,
+            },
+            (Some(start), Some(silence)) if window_end - silence >= self.min_silence_samples => {
+                Utterance {
+                    start_sample: start,
+                    end_sample: silence,
+                    reason: UtteranceEndReason::Silence,
+                }
+            }
+            (Some(start), _) if window_end - start >= self.max_utterance_samples => Utterance {
+                start_sample: start,
+                end_sample: window_end,
+                reason: UtteranceEndReason::MaxLength,
+            },
+            _ => return None,
+        };
+
+        self.listening = None;
+        Some(utterance)
+    }
+}
+
+/// A speech detector scoring fixed-size windows of audio
+pub trait VoiceActivity: Send {
+    /// Speech probability (0.0 to 1.0) of one `chunk_size` window of samples
+    /// in the [-1.0, 1.0] range. Windows arrive in order without gaps.
+    fn predict(&mut self, window: &[f32]) -> f32;
+}
+
+/// Silero VAD from the `voice_activity_detector` crate
+#[cfg(feature = "vad")]
+pub struct SileroVad {
+    detector: VoiceActivityDetector,
+}
+
+#[cfg(feature = "vad")]
+impl SileroVad {
+    pub fn new(config: &VadConfig) -> Result<Self, VadError> {
+        let detector = VoiceActivityDetector::builder()
+            .chunk_size(config.chunk_size)
+            .sample_rate(config.sample_rate as i64)
+            .build()
+            .map_err(|e| VadError::InitializationError(e.to_string()))?;
+        Ok(Self { detector })
+    }
+}
+
+#[cfg(feature = "vad")]
+impl VoiceActivity for SileroVad {
+    fn predict(&mut self, window: &[f32]) -> f32 {
+        self.detector.predict(window.iter().copied())
+    }
+}
+
+/// Lightweight WebRTC-style detector: a window is speech when its energy
+/// stands well above an adaptive noise floor and its zero-crossing rate is
+/// not noise-like. Needs no model, so it suits low-power builds.
+pub struct EnergyVad {
+    /// Tracked background level in dBFS, `None` until the first window
+    noise_floor_db: Option<f32>,
+}
+
+impl EnergyVad {
+    /// Energy above the noise floor at which the probability reaches 0.5
+    const SPEECH_MARGIN_DB: f32 = 9.0;
+    /// Width of the transition around the margin
+    const SLOPE_DB: f32 = 2.0;
+    /// Windows quieter than this are never speech
+    const MIN_SPEECH_DBFS: f32 = -65.0;
+    /// Above this rate of sign changes per sample a window sounds like hiss
+    const MAX_SPEECH_ZCR: f32 = 0.35;
+    /// How fast the noise floor may rise per window (~8 dB/s at 32ms windows)
+    const FLOOR_RISE_DB: f32 = 0.25;
+
+    pub fn new() -> Self {
+        Self {
+            noise_floor_db: None,
+        }
+    }
+}
+
+impl Default for EnergyVad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoiceActivity for EnergyVad {
+    fn predict(&mut self, window: &[f32]) -> f32 {
+        if window.is_empty() {
+            return 0.0;
+        }
+        let mean_square = window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32;
+        let energy_db = 10.0 * mean_square.max(1e-10).log10();
+        let crossings = window
+            .windows(2)
+            .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+            .count();
+        let zcr = crossings as f32 / window.len() as f32;
+
+        // The floor drops to quieter windows at once but rises only slowly,
+        // so speech doesn't raise it while steady noise eventually does. It
+        // never goes below the speech minimum, so digital silence can't make
+        // every later sound look like speech.
+        let floor = match self.noise_floor_db {
+            Some(floor) if energy_db < floor => energy_db,
+            Some(floor) => (floor + Self::FLOOR_RISE_DB).min(energy_db),
+            None => energy_db,
+        }
+        .max(Self::MIN_SPEECH_DBFS);
+        self.noise_floor_db = Some(floor);
+
+        if energy_db < Self::MIN_SPEECH_DBFS {
+            return 0.0;
+        }
+        let snr_db = energy_db - floor;
+        let probability = 1.0 / (1.0 + (-(snr_db - Self::SPEECH_MARGIN_DB) / Self::SLOPE_DB).exp());
+        if zcr > Self::MAX_SPEECH_ZCR {
+            probability * 0.5
+        } else {
+            probability
         }
     }
 }
 
 /// Voice Activity Detector with buffering for 1280→512 sample processing
 pub struct VadProcessor {
-    detector: VoiceActivityDetector,
+    detector: Box<dyn VoiceActivity>,
     config: VadConfig,
     remainder_buffer: Vec<f32>, // Buffer for samples that don't fit in 512-sample chunks
 }
@@ -33,19 +392,24 @@ pub struct VadProcessor {
 impl VadProcessor {
     /// Create a new VAD processor with the given configuration
     pub fn new(config: VadConfig) -> Result<Self, VadError> {
-        // Create the VAD detector using the builder pattern
-        let detector = VoiceActivityDetector::builder()
-            .chunk_size(config.chunk_size)
-            .sample_rate(config.sample_rate as i64)
-            .build()
-            .map_err(|e| VadError::InitializationError(e.to_string()))?;
+        let detector: Box<dyn VoiceActivity> = match config.backend {
+            #[cfg(feature = "vad")]
+            VadBackend::Silero => Box::new(SileroVad::new(&config)?),
+            #[cfg(not(feature = "vad"))]
+            VadBackend::Silero => {
+                return Err(VadError::InitializationError(
+                    "Silero VAD needs the `vad` feature; use the energy backend".to_string(),
+                ))
+            }
+            VadBackend::Energy => Box::new(EnergyVad::new()),
+        };
 
         // Calculate chunk duration based on chunk size and sample rate
         let chunk_duration_ms = (config.chunk_size as u64 * 1000) / config.sample_rate as u64;
 
         info!(
-            "🎤 VAD initialized: chunk_size={}, sample_rate={}Hz, chunk_duration={}ms",
-            config.chunk_size, config.sample_rate, chunk_duration_ms
+            "🎤 VAD initialized: backend={:?}, chunk_size={}, sample_rate={}Hz, chunk_duration={}ms",
+            config.backend, config.chunk_size, config.sample_rate, chunk_duration_ms
         );
 
         Ok(Self {
@@ -66,6 +430,19 @@ impl VadProcessor {
     /// # Returns
     /// * `bool` - true if any speech was detected in this chunk, false otherwise
     pub fn analyze_chunk(&mut self, audio_data: &[u8]) -> Result<bool, VadError> {
+        let windows = self.analyze_windows(audio_data, 0)?;
+        Ok(windows.iter().any(|w| w.has_speech))
+    }
+
+    /// Analyze a chunk like `analyze_chunk`, but return the speech probability
+    /// and decision for every 512-sample window completed by it. `sample_index` is the capture index
+    /// of the chunk's first sample; a window that started in the previous
+    /// chunk is reported at its true (earlier) index.
+    pub fn analyze_windows(
+        &mut self,
+        audio_data: &[u8],
+        sample_index: u64,
+    ) -> Result<Vec<VadWindow>, VadError> {
         // Convert audio data to f32 samples for VAD processing
         let new_samples: Vec<f32> = audio_data
             .chunks_exact(2)
@@ -89,7 +466,7 @@ impl VadProcessor {
             );
         }
 
-        let mut any_speech = false;
+        let mut windows = Vec::with_capacity(new_samples.len() / 512 + 1);
         let mut processed_offset = 0;
 
         // If we have remainder from previous chunk, process it first
@@ -110,7 +487,7 @@ impl VadProcessor {
                 );
 
                 // Process the combined 512-sample chunk
-                let speech_prob = self.detector.predict(combined.iter().copied());
+                let speech_prob = self.detector.predict(&combined);
                 let has_speech = speech_prob >= self.config.speech_threshold;
 
                 debug!(
@@ -118,9 +495,11 @@ impl VadProcessor {
                     speech_prob, has_speech
                 );
 
-                if has_speech {
-                    any_speech = true;
-                }
+                windows.push(VadWindow {
+                    sample_index: sample_index.saturating_sub(self.remainder_buffer.len() as u64),
+                    probability: speech_prob,
+                    has_speech,
+                });
 
                 processed_offset = samples_needed; // Skip samples we just processed
                 self.remainder_buffer.clear(); // Buffer is now empty
@@ -135,14 +514,14 @@ impl VadProcessor {
                 );
 
                 // All samples are now in the buffer, nothing more to process
-                return Ok(any_speech);
+                return Ok(windows);
             }
         }
 
         // Process remaining complete 512-sample chunks from new samples
         let remaining_samples = &new_samples[processed_offset..];
         for (i, chunk_512) in remaining_samples.chunks_exact(512).enumerate() {
-            let speech_prob = self.detector.predict(chunk_512.iter().copied());
+            let speech_prob = self.detector.predict(chunk_512);
             let has_speech = speech_prob >= self.config.speech_threshold;
 
             debug!(
@@ -150,9 +529,11 @@ impl VadProcessor {
                 i, speech_prob, has_speech
             );
 
-            if has_speech {
-                any_speech = true;
-            }
+            windows.push(VadWindow {
+                sample_index: sample_index + (processed_offset + i * 512) as u64,
+                probability: speech_prob,
+                has_speech,
+            });
         }
 
         // Save any remainder for next chunk
@@ -166,12 +547,13 @@ impl VadProcessor {
         }
 
         debug!(
-            "🎤 VAD result: any_speech={}, processed {} total samples",
-            any_speech,
+            "🎤 VAD result: {} windows, {} with speech, processed {} total samples",
+            windows.len(),
+            windows.iter().filter(|w| w.has_speech).count(),
             new_samples.len()
         );
 
-        Ok(any_speech)
+        Ok(windows)
     }
 
     /// Reset the VAD processor state (clear remainder buffer)
@@ -185,3 +567,192 @@ impl VadProcessor {
         self.remainder_buffer.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(segmenter: &mut SpeechSegmenter, start: u64, pattern: &str) -> Vec<SpeechEvent> {
+        pattern
+            .chars()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                segmenter.push(&VadWindow {
+                    sample_index: start + i as u64 * 512,
+                    probability: if c == 'S' { 0.9 } else { 0.1 },
+                    has_speech: c == 'S',
+                })
+            })
+            .collect()
+    }
+
+    fn endpoint(endpointer: &mut Endpointer, start: u64, pattern: &str) -> Option<Utterance> {
+        pattern.chars().enumerate().find_map(|(i, c)| {
+            endpointer.push(&VadWindow {
+                sample_index: start + i as u64 * 512,
+                probability: if c == 'S' { 0.9 } else { 0.1 },
+                has_speech: c == 'S',
+            })
+        })
+    }
+
+    #[test]
+    fn test_endpointer_reasons() {
+        // 2 windows of silence, 8 windows max, 4 windows without speech
+        let config = EndpointConfig {
+            min_silence_ms: 64,
+            max_utterance_ms: 256,
+            no_speech_timeout_ms: 128,
+        };
+        let mut endpointer = Endpointer::new(&config, &VadConfig::default());
+
+        // Nothing happens until a wake word starts listening
+        assert_eq!(endpoint(&mut endpointer, 0, "SSSSSSSS"), None);
+
+        // Windows before the start are ignored; a short pause doesn't end it
+        endpointer.start(10 * 512);
+        assert_eq!(
+            endpoint(&mut endpointer, 8 * 512, "SS.SS.S.."),
+            Some(Utterance {
+                start_sample: 11 * 512,
+                end_sample: 15 * 512,
+                reason: UtteranceEndReason::Silence,
+            })
+        );
+        assert!(!endpointer.is_listening());
+
+        endpointer.start(0);
+        assert_eq!(
+            endpoint(&mut endpointer, 0, "....S"),
+            Some(Utterance {
+                start_sample: 0,
+                end_sample: 4 * 512,
+                reason: UtteranceEndReason::NoSpeech,
+            })
+        );
+
+        endpointer.start(0);
+        assert_eq!(
+            endpoint(&mut endpointer, 0, ".SSSSSSSS"),
+            Some(Utterance {
+                start_sample: 512,
+                end_sample: 9 * 512,
+                reason: UtteranceEndReason::MaxLength,
+            })
+        );
+    }
+
+    #[test]
+    fn test_segmenter_onset_and_hangover() {
+        // 3 windows of onset, 5 windows of hangover
+        let config = VadConfig {
+            onset_ms: 96,
+            hangover_ms: 160,
+            ..VadConfig::default()
+        };
+        let mut segmenter = SpeechSegmenter::new(&config);
+
+        // Two-window blips never start a segment
+        assert!(feed(&mut segmenter, 0, "..SS..SS..").is_empty());
+        assert!(!segmenter.in_speech());
+
+        // Speech starts at the first window of the run that lasted long enough
+        let events = feed(&mut segmenter, 10 * 512, "SSSS");
+        assert_eq!(
+            events,
+            vec![SpeechEvent::Started {
+                sample_index: 10 * 512
+            }]
+        );
+
+        // Short pauses are bridged; the stop is dated to where silence began
+        let events = feed(&mut segmenter, 14 * 512, "..SS.....");
+        assert_eq!(
+            events,
+            vec![SpeechEvent::Stopped {
+                sample_index: 18 * 512
+            }]
+        );
+        assert!(!segmenter.in_speech());
+    }
+
+    fn tone(amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 200.0 * i as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    fn hiss(amplitude: f32, len: usize) -> Vec<f32> {
+        let mut state = 1u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                amplitude * ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_energy_vad() {
+        let mut vad = EnergyVad::new();
+
+        // A quiet room sets the noise floor; a voiced sound well above it is speech
+        for _ in 0..10 {
+            assert!(vad.predict(&hiss(0.003, 512)) < 0.1);
+        }
+        assert!(vad.predict(&tone(0.1, 512)) > 0.9);
+
+        // Loud hiss crosses lots of zeros and stays below the speech threshold
+        assert!(vad.predict(&hiss(0.1, 512)) < 0.5);
+
+        // A steady sound becomes background once the floor catches up
+        for _ in 0..200 {
+            vad.predict(&tone(0.1, 512));
+        }
+        assert!(vad.predict(&tone(0.1, 512)) < 0.5);
+
+        // Digital silence is never speech
+        assert_eq!(vad.predict(&[0.0; 512]), 0.0);
+    }
+
+    #[test]
+    fn test_energy_backend_windows() {
+        let mut vad = VadProcessor::new(VadConfig {
+            backend: VadBackend::Energy,
+            ..VadConfig::default()
+        })
+        .unwrap();
+        let to_bytes = |samples: Vec<f32>| -> Vec<u8> {
+            samples
+                .iter()
+                .flat_map(|s| ((s * 32767.0) as i16).to_le_bytes())
+                .collect()
+        };
+
+        let first = vad
+            .analyze_windows(&to_bytes(hiss(0.003, 1280)), 0)
+            .unwrap();
+        assert_eq!(
+            first.iter().map(|w| w.sample_index).collect::<Vec<_>>(),
+            vec![0, 512]
+        );
+        assert!(first.iter().all(|w| !w.has_speech));
+
+        // The window straddling both chunks keeps its true start
+        let second = vad
+            .analyze_windows(&to_bytes(tone(0.1, 1280)), 1280)
+            .unwrap();
+        assert_eq!(
+            second.iter().map(|w| w.sample_index).collect::<Vec<_>>(),
+            vec![1024, 1536, 2048]
+        );
+        assert!(second[1].has_speech && second[1].probability > 0.9);
+
+        assert_eq!("energy".parse::<VadBackend>(), Ok(VadBackend::Energy));
+        assert_eq!(
+            VadBackend::Silero.to_string().parse(),
+            Ok(VadBackend::Silero)
+        );
+        assert!("webrtc".parse::<VadBackend>().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+#!/usr/bin/env python3
+"""Generate golden reference outputs for the wake word feature pipeline.
+
+For every `tests/golden/<clip>.raw` (16kHz mono s16le) this writes
+`<clip>.reference.json` with the Python openWakeWord melspectrogram frames,
+embeddings and per-hop (1280 sample) scores, using the models in `models/`.
+The Rust test in `tests/wakeword_golden.rs` compares against these files.
+
+    pip install openwakeword==0.6.0 tflite-runtime numpy
+    python3 tests/golden/generate_reference.py
+
+`--synthesize` (re)creates the deterministic `synthetic_sweep.raw` clip and
+needs only the standard library.
+"""
+
+import argparse
+import json
+import math
+import random
+import struct
+from pathlib import Path
+
+GOLDEN_DIR = Path(__file__).resolve().parent
+MODEL_DIR = GOLDEN_DIR.parent.parent / "models"
+SAMPLE_RATE = 16000
+HOP = 1280
+
+
+def synthesize(path):
+    """2s of quiet noise around a voiced, harmonic pitch sweep"""
+    rng = random.Random(42)
+    samples = []
+    phase = 0.0
+    for n in range(2 * SAMPLE_RATE):
+        t = n / SAMPLE_RATE
+        value = rng.gauss(0.0, 60.0)
+        if 0.5 <= t < 1.5:
+            f0 = 120.0 + 100.0 * (t - 0.5)
+            phase += 2.0 * math.pi * f0 / SAMPLE_RATE
+            envelope = math.sin(math.pi * (t - 0.5))
+            voice = sum(math.sin(k * phase) / k for k in range(1, 12))
+            value += 3000.0 * envelope * voice
+        samples.append(max(-32768, min(32767, int(round(value)))))
+    path.write_bytes(struct.pack("<%dh" % len(samples), *samples))
+
+
+def manifest_path(name):
+    manifest = json.loads((MODEL_DIR / f"{name}.json").read_text())
+    return str(MODEL_DIR / manifest["path"])
+
+
+def reference(clip, model_name):
+    import numpy as np
+    from openwakeword.model import Model
+    from openwakeword.utils import AudioFeatures
+
+    samples = np.fromfile(clip, dtype="<i2")
+    feature_paths = {
+        "melspec_model_path": manifest_path("melspectrogram"),
+        "embedding_model_path": manifest_path("embedding"),
+    }
+
+    features = AudioFeatures(inference_framework="tflite", **feature_paths)
+    melspectrogram = features._get_melspectrogram(samples)
+    embeddings = features._get_embeddings(samples)
+
+    model = Model(
+        wakeword_models=[manifest_path(model_name)],
+        inference_framework="tflite",
+        **feature_paths,
+    )
+    scores = []
+    for start in range(0, len(samples) - HOP + 1, HOP):
+        prediction = model.predict(samples[start : start + HOP])
+        scores.append(float(next(iter(prediction.values()))))
+
+    return {
+        "model": model_name,
+        "source": "openwakeword (python)",
+        "melspectrogram": np.asarray(melspectrogram).tolist(),
+        "embeddings": np.asarray(embeddings).tolist(),
+        "scores": scores,
+    }
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--model", default="hey_mycroft")
+    parser.add_argument("--synthesize", action="store_true")
+    args = parser.parse_args()
+
+    if args.synthesize:
+        synthesize(GOLDEN_DIR / "synthetic_sweep.raw")
+
+    for clip in sorted(GOLDEN_DIR.glob("*.raw")):
+        out = clip.with_suffix(".reference.json")
+        out.write_text(json.dumps(reference(clip, args.model)) + "\n")
+        print(f"wrote {out.name}")
+
+
+if __name__ == "__main__":
+    main()
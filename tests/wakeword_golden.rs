@@ -0,0 +1,206 @@
+//! Golden-file regression tests for the wake word feature pipeline.
+//!
+//! Every `tests/golden/<clip>.raw` (16kHz mono s16le) is run through
+//! `AudioFeatures` and `Model`, and the melspectrogram frames, embeddings and
+//! per-hop scores are compared with `<clip>.reference.json` within an
+//! absolute tolerance. A clip without a reference fails. References come
+//! from the Python openWakeWord implementation via
+//! `tests/golden/generate_reference.py`, so a change to the tflitec fork or
+//! the buffering logic that drifts from it fails here.
+//!
+//! Run with `GOLDEN_BLESS=1` to write the current Rust outputs as references
+//! instead, e.g. to pin down behaviour on a device without Python.
+
+#![cfg(feature = "wakeword")]
+
+use audio::wakeword_model::Model;
+use audio::wakeword_models::{ModelKind, ModelRegistry};
+use audio::wakeword_utils::AudioFeatures;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+const MODEL_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/models");
+const DEFAULT_MODEL: &str = "hey_mycroft";
+const HOP: usize = 1280;
+
+/// Default tolerances; a reference can override them under `"tolerance"`
+const MELSPEC_TOLERANCE: f64 = 0.01;
+const EMBEDDING_TOLERANCE: f64 = 0.01;
+const SCORE_TOLERANCE: f64 = 0.005;
+
+struct Outputs {
+    melspectrogram: Vec<Vec<f32>>,
+    embeddings: Vec<Vec<f32>>,
+    scores: Vec<f32>,
+}
+
+fn run_pipeline(samples: &[i16], model_name: &str) -> Outputs {
+    let registry = ModelRegistry::discover(MODEL_DIR).unwrap();
+    let melspec = registry.feature(ModelKind::Melspectrogram).unwrap();
+    let embedding = registry.feature(ModelKind::Embedding).unwrap();
+    let mut features = AudioFeatures::new(
+        &melspec.path.to_string_lossy(),
+        &embedding.path.to_string_lossy(),
+        16000,
+    )
+    .unwrap();
+
+    let melspectrogram = features
+        .melspectrogram(samples)
+        .unwrap()
+        .chunks(32)
+        .map(<[f32]>::to_vec)
+        .collect();
+    let embeddings = features.embeddings(samples).unwrap();
+
+    // Scores stream through a fresh model one hop at a time, like the service
+    let mut model =
+        Model::new_with_model_path(vec![model_name.to_string()], vec![], MODEL_DIR).unwrap();
+    let scores = samples
+        .chunks_exact(HOP)
+        .map(|hop| {
+            let predictions = model.predict(hop, None, 1.0).unwrap();
+            predictions.get(model_name).copied().unwrap_or_default()
+        })
+        .collect();
+
+    Outputs {
+        melspectrogram,
+        embeddings,
+        scores,
+    }
+}
+
+fn read_clip(path: &Path) -> Vec<i16> {
+    fs::read(path)
+        .unwrap()
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+/// Compare `actual` rows with the reference array `expected`, describing the
+/// first shape mismatch or the largest difference above `tolerance`
+fn compare(what: &str, actual: &[Vec<f32>], expected: &Value, tolerance: f64) -> Option<String> {
+    let Some(expected) = expected.as_array() else {
+        return Some(format!("{}: missing from reference", what));
+    };
+    if actual.len() != expected.len() {
+        return Some(format!(
+            "{}: {} rows, reference has {}",
+            what,
+            actual.len(),
+            expected.len()
+        ));
+    }
+
+    let mut worst: Option<(usize, usize, f64)> = None;
+    for (row, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        let expected: Vec<f64> = match expected {
+            Value::Array(values) => values.iter().filter_map(Value::as_f64).collect(),
+            value => value.as_f64().into_iter().collect(),
+        };
+        if actual.len() != expected.len() {
+            return Some(format!(
+                "{}: row {} has {} values, reference has {}",
+                what,
+                row,
+                actual.len(),
+                expected.len()
+            ));
+        }
+        for (col, (&a, &e)) in actual.iter().zip(&expected).enumerate() {
+            let diff = (a as f64 - e).abs();
+            if diff > tolerance && worst.is_none_or(|(_, _, d)| diff > d) {
+                worst = Some((row, col, diff));
+            }
+        }
+    }
+
+    worst.map(|(row, col, diff)| {
+        format!(
+            "{}: [{}][{}] differs by {:.6} (tolerance {})",
+            what, row, col, diff, tolerance
+        )
+    })
+}
+
+fn bless(clip: &Path, reference_path: &Path, samples: &[i16]) {
+    let outputs = run_pipeline(samples, DEFAULT_MODEL);
+    let reference = json!({
+        "model": DEFAULT_MODEL,
+        "source": "audio crate (GOLDEN_BLESS)",
+        "melspectrogram": outputs.melspectrogram,
+        "embeddings": outputs.embeddings,
+        "scores": outputs.scores,
+    });
+    fs::write(reference_path, format!("{}\n", reference)).unwrap();
+    eprintln!("blessed {}", clip.display());
+}
+
+#[test]
+fn test_golden_references() {
+    let mut clips: Vec<PathBuf> = fs::read_dir(GOLDEN_DIR)
+        .unwrap()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "raw"))
+        .collect();
+    clips.sort();
+    assert!(!clips.is_empty(), "no clips in {}", GOLDEN_DIR);
+
+    let mut failures = Vec::new();
+    for clip in &clips {
+        let samples = read_clip(clip);
+        let reference_path = clip.with_extension("reference.json");
+        if std::env::var_os("GOLDEN_BLESS").is_some() {
+            bless(clip, &reference_path, &samples);
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&reference_path) else {
+            failures.push(format!(
+                "{}: no reference, run tests/golden/generate_reference.py",
+                clip.display()
+            ));
+            continue;
+        };
+
+        let reference: Value = serde_json::from_str(&text).unwrap();
+        let model_name = reference["model"].as_str().unwrap_or(DEFAULT_MODEL);
+        let tolerance =
+            |key: &str, default: f64| reference["tolerance"][key].as_f64().unwrap_or(default);
+        let outputs = run_pipeline(&samples, model_name);
+
+        let scores: Vec<Vec<f32>> = outputs.scores.iter().map(|&s| vec![s]).collect();
+        let checks = [
+            compare(
+                "melspectrogram",
+                &outputs.melspectrogram,
+                &reference["melspectrogram"],
+                tolerance("melspectrogram", MELSPEC_TOLERANCE),
+            ),
+            compare(
+                "embeddings",
+                &outputs.embeddings,
+                &reference["embeddings"],
+                tolerance("embeddings", EMBEDDING_TOLERANCE),
+            ),
+            compare(
+                "scores",
+                &scores,
+                &reference["scores"],
+                tolerance("scores", SCORE_TOLERANCE),
+            ),
+        ];
+        let name = clip.file_name().unwrap().to_string_lossy();
+        failures.extend(
+            checks
+                .into_iter()
+                .flatten()
+                .map(|f| format!("{}: {}", name, f)),
+        );
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}